@@ -4,10 +4,10 @@ mod std_lib;
 mod vm;
 
 pub use bytecode_emitter::BytecodeEmitter;
-pub use vm::VM;
+pub use vm::{PrintFlush, VM};
 
 #[allow(non_camel_case_types, clippy::upper_case_acronyms, dead_code)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum OpCode {
     /// Do nothing.
     NOP,
@@ -32,6 +32,9 @@ pub enum OpCode {
     /// TOS must be a generator object. If TOS.\_\_is_done\_\_ is true, pop TOS and increment IP by /0/ instructions.
     /// Otherwise, call next(TOS) and push the next value on the stack.
     FOR_ITER(usize),
+    /// TOS must be a count of the items beneath it. If the count is 0, pop TOS and increment IP by /0/ instructions.
+    /// Otherwise, decrement the count and move TOS1 above it.
+    FOR_STACK(usize),
     /// Store TOS in local variable /0/. Pop TOS.
     STORE_LOCAL(usize),
     /// Store TOS in deref (cell or free) variable /0/. Pop TOS.
@@ -58,8 +61,11 @@ pub enum OpCode {
     LOAD_ATTR(usize),
     /// Replace TOS with TOS1\[TOS\]. Uses TOS1.\_\_getitem\_\_().
     LOAD_ACCESS,
-    /// Make a function object with /0/ args and const code object /1/. Push result.
-    MAKE_FUNCTION(usize, usize),
+    /// Call TOS.<const string /0/>(TOS1), falling back to TOS1's reflected method if it is missing or returns
+    /// NotImplemented. Pop TOS..TOS1, push result.
+    BINARY_OP(usize),
+    /// Make a function object named const /2/ with /0/ args and const code object /1/. Push result.
+    MAKE_FUNCTION(usize, usize, usize),
     /// Call TOS.\_\_call\_\_() with /0/ arguments. Pop TOS..TOS/0/, push result.
     CALL_FUNCTION(usize),
     /// Build a list with items TOS..TOS{ /0/-1 } in that order. Pop TOS..TOS{ /0/-1 }, push the new list.
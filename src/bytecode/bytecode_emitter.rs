@@ -6,7 +6,7 @@ use std::rc::Rc;
 
 use ordered_float::OrderedFloat;
 
-use super::{OpCode, objects::*};
+use super::{OpCode, objects::*, std_lib};
 use crate::bytecode::objects::Object;
 use crate::parser::ptag::{AstNode, OperationTree};
 use crate::parser::{building_blocks::*, markers::*, symbol_table::SymbolTable};
@@ -59,12 +59,13 @@ fn display_bytecode(
             OpCode::JUMP_ABSOLUTE(n) => write!(f, "JUMP_ABSOLUTE {n}")?,
             OpCode::MAKE_GENERATOR => write!(f, "MAKE_GENERATOR")?,
             OpCode::FOR_ITER(n) => write!(f, "FOR_ITER {n}")?,
+            OpCode::FOR_STACK(n) => write!(f, "FOR_STACK {n}")?,
             OpCode::STORE_LOCAL(n) => write!(f, "STORE_LOCAL {n}")?,
             OpCode::STORE_DEREF(n) => write!(f, "STORE_DEREF {n}")?,
             OpCode::STORE_GLOBAL(n) => {
                 let name = constants_pool
                     .get(*n)
-                    .expect(&format!("Constant {n} should exist"));
+                    .unwrap_or_else(|| panic!("Constant {n} should exist"));
                 let Object::String(name) = &*name.borrow() else {
                     panic!("Constant {n} should be a string");
                 };
@@ -73,7 +74,7 @@ fn display_bytecode(
             OpCode::STORE_ATTR(n) => {
                 let attr = constants_pool
                     .get(*n)
-                    .expect(&format!("Constant {n} should exist"));
+                    .unwrap_or_else(|| panic!("Constant {n} should exist"));
                 let Object::String(attr) = &*attr.borrow() else {
                     panic!("Constant {n} should be a string");
                 };
@@ -83,10 +84,10 @@ fn display_bytecode(
             OpCode::LOAD_CONST(n) => {
                 let c = constants_pool
                     .get(*n)
-                    .expect(&format!("Constant {n} should exist"));
+                    .unwrap_or_else(|| panic!("Constant {n} should exist"));
                 let c_display = match &*c.borrow() {
                     Object::None => "None".to_string(),
-                    Object::Number(num) => format!("{num}"),
+                    Object::Number(num) => std_lib::number::display(*num),
                     Object::Boolean(b) => (if *b { "True" } else { "False" }).to_string(),
                     Object::String(s) => format!("'{s}'"),
                     Object::Code(code_object) => format!("Code({code_object:p})"),
@@ -101,7 +102,7 @@ fn display_bytecode(
             OpCode::LOAD_GLOBAL(n) => {
                 let name = constants_pool
                     .get(*n)
-                    .expect(&format!("Constant {n} should exist"));
+                    .unwrap_or_else(|| panic!("Constant {n} should exist"));
                 let Object::String(name) = &*name.borrow() else {
                     panic!("Constant {n} should be a string");
                 };
@@ -110,22 +111,37 @@ fn display_bytecode(
             OpCode::LOAD_ATTR(n) => {
                 let attr = constants_pool
                     .get(*n)
-                    .expect(&format!("Constant {n} should exist"));
+                    .unwrap_or_else(|| panic!("Constant {n} should exist"));
                 let Object::String(attr) = &*attr.borrow() else {
                     panic!("Constant {n} should be a string");
                 };
                 write!(f, "LOAD_ATTR '{attr}'")?
             }
             OpCode::LOAD_ACCESS => write!(f, "LOAD_ACCESS")?,
-            OpCode::MAKE_FUNCTION(n, m) => {
+            OpCode::BINARY_OP(n) => {
+                let method = constants_pool
+                    .get(*n)
+                    .unwrap_or_else(|| panic!("Constant {n} should exist"));
+                let Object::String(method) = &*method.borrow() else {
+                    panic!("Constant {n} should be a string");
+                };
+                write!(f, "BINARY_OP '{method}'")?
+            }
+            OpCode::MAKE_FUNCTION(n, m, name_idx) => {
+                let name = constants_pool
+                    .get(*name_idx)
+                    .unwrap_or_else(|| panic!("Constant {name_idx} should exist"));
+                let Object::String(name) = &*name.borrow() else {
+                    panic!("Constant {name_idx} should be a string");
+                };
                 let func = constants_pool
                     .get(*m)
-                    .expect(&format!("Constant {n} should exist"))
+                    .unwrap_or_else(|| panic!("Constant {n} should exist"))
                     .clone();
                 let Object::Code(ref func_code) = *func.borrow() else {
                     panic!("This constant is a non-const type");
                 };
-                write!(f, "MAKE_FUNCTION {n}, Code({func_code:p})")?;
+                write!(f, "MAKE_FUNCTION '{name}' {n}, Code({func_code:p})")?;
             }
             OpCode::CALL_FUNCTION(n) => write!(f, "CALL_FUNCTION {n}")?,
             OpCode::BUILD_LIST(n) => write!(f, "BUILD_LIST {n}")?,
@@ -159,6 +175,15 @@ struct LoopContext {
     break_points: Vec<usize>,
 }
 
+/// What an assignment stores its value into, see `assign_op()`.
+#[derive(Debug, Clone, Copy)]
+enum AssignTarget<'a> {
+    /// A variable and any accesses into it, as in `a[0][1] = value`
+    Variable(&'a MarkedString, &'a [MarkedOperationTree]),
+    /// The last access into anything else, as in `f()[0] = value`
+    Subscript(&'a MarkedOperationTree, &'a MarkedOperationTree),
+}
+
 #[derive(Debug)]
 pub struct BytecodeEmitter {
     is_emitted: bool,
@@ -254,16 +279,46 @@ impl BytecodeEmitter {
                 parameters,
                 body,
             } => self.function_def(identifier, parameters, body),
+            // A call on its own is a statement, so its result is thrown away
             AstNode::function_call {
                 function,
                 arguments,
-            } => self.function_call(function, arguments),
+            } => {
+                let mut total = self.function_call(function, arguments);
+                self.instructions.push(OpCode::POP_TOP);
+                total.0 += 1;
+                total
+            }
+            AstNode::call { callee, arguments } => {
+                let mut total = self.call(callee, arguments);
+                self.instructions.push(OpCode::POP_TOP);
+                total.0 += 1;
+                total
+            }
             AstNode::assign_op {
                 variable,
                 accesses,
+                chained,
                 asop,
                 value,
-            } => self.assign_op(variable, accesses, asop, value),
+            } => self.assign_op(
+                AssignTarget::Variable(variable, accesses),
+                chained,
+                asop,
+                value,
+            ),
+            AstNode::subscript_assign_op {
+                container,
+                access,
+                chained,
+                asop,
+                value,
+            } => self.assign_op(
+                AssignTarget::Subscript(container, access),
+                chained,
+                asop,
+                value,
+            ),
             bad => panic!("Tried using BytecodeEmitter::ast() on {bad:?}"),
         };
 
@@ -382,6 +437,7 @@ impl BytecodeEmitter {
     /// STORE_{LOCAL|DEREF|GLOBAL}
     /// Body
     /// JUMP_ABSOLUTE
+    /// [POP_TOP]    (pops the generator after a `break`, FOR_ITER pops it otherwise)
     /// ```
     fn for_loop(
         &mut self,
@@ -390,12 +446,20 @@ impl BytecodeEmitter {
         body: &MarkedAstNode,
     ) -> Emissions {
         debug!("BytecodeEmitter::for_loop() started");
+        if let OperationTree::Identity(MarkedAstNode {
+            comp: AstNode::list(items),
+            ..
+        }) = &iterator.comp
+        {
+            return self.list_literal_for_loop(loop_variable, items, body);
+        }
         let mut total = Emissions(0);
 
         total += self.operation_tree(iterator);
         self.instructions.push(OpCode::MAKE_GENERATOR);
         total.0 += 1;
         let loop_ip = self.instructions.len();
+        // `continue` jumps to the FOR_ITER itself, so the generator advances instead of re-yielding
         self.loop_contexts.push(LoopContext {
             start: loop_ip,
             break_points: Vec::new(),
@@ -403,29 +467,113 @@ impl BytecodeEmitter {
         self.instructions.push(OpCode::NOP);
         total.0 += 1;
         total += self.emit_store(loop_variable);
-        let body_size = self.ast(body);
-        total += body_size;
+        total += self.ast(body);
         self.instructions.push(OpCode::JUMP_ABSOLUTE(loop_ip));
         total.0 += 1;
+
+        // Only a `break` leaves the generator on the stack
+        let break_points = self
+            .loop_contexts
+            .pop()
+            .expect("Loop context was not set")
+            .break_points;
+        if !break_points.is_empty() {
+            let break_ip = self.instructions.len();
+            self.instructions.push(OpCode::POP_TOP);
+            total.0 += 1;
+            // Replace all break NOPs with JUMP_ABSOLUTE
+            for br in break_points {
+                *self.instructions.get_mut(br).expect("Break wasn't found") =
+                    OpCode::JUMP_ABSOLUTE(break_ip);
+            }
+        }
         let loop_end = self.instructions.len();
 
         // Replace NOP with FOR_ITER
         *self
             .instructions
             .get_mut(loop_ip)
-            .expect("Instruction wasn't found") = OpCode::FOR_ITER(body_size.0 + 3);
-        // Replace all break NOPs with JUMP_ABSOLUTE
-        for br in self
+            .expect("Instruction wasn't found") = OpCode::FOR_ITER(loop_end - loop_ip);
+
+        debug!("BytecodeEmitter::for_loop() ended");
+        total
+    }
+
+    /// A `for` loop over a list literal, without building the list or a generator. The items are pushed onto the stack
+    /// once, underneath a count of how many are left, and every iteration takes the next one off with FOR_STACK.
+    ///
+    /// ```
+    /// Item N
+    /// ...
+    /// Item 1
+    /// LOAD_CONST
+    /// FOR_STACK
+    /// STORE_{LOCAL|DEREF|GLOBAL}
+    /// Body
+    /// JUMP_ABSOLUTE
+    /// [FOR_STACK]    (these three drain the items left over after a `break`)
+    /// [POP_TOP]
+    /// [JUMP_ABSOLUTE]
+    /// ```
+    fn list_literal_for_loop(
+        &mut self,
+        loop_variable: &MarkedString,
+        items: &[MarkedOperationTree],
+        body: &MarkedAstNode,
+    ) -> Emissions {
+        debug!("BytecodeEmitter::list_literal_for_loop() started");
+        let mut total = Emissions(0);
+
+        // Same order as a list literal, so the first item ends up on top
+        for item in items.iter().rev() {
+            total += self.operation_tree(item);
+        }
+        let count_idx = self.const_num(&MarkedNumber::from(items.len() as f64)).0;
+        self.instructions.push(OpCode::LOAD_CONST(count_idx));
+        total.0 += 1;
+        let loop_ip = self.instructions.len();
+        self.loop_contexts.push(LoopContext {
+            start: loop_ip,
+            break_points: Vec::new(),
+        });
+        self.instructions.push(OpCode::NOP);
+        total.0 += 1;
+        let mut for_stack_ips = vec![loop_ip];
+        total += self.emit_store(loop_variable);
+        total += self.ast(body);
+        self.instructions.push(OpCode::JUMP_ABSOLUTE(loop_ip));
+        total.0 += 1;
+
+        // Only a `break` can leave items on the stack
+        let break_points = self
             .loop_contexts
             .pop()
             .expect("Loop context was not set")
-            .break_points
-        {
-            *self.instructions.get_mut(br).expect("Break wasn't found") =
-                OpCode::JUMP_ABSOLUTE(loop_end);
+            .break_points;
+        if !break_points.is_empty() {
+            let drain_ip = self.instructions.len();
+            for_stack_ips.push(drain_ip);
+            self.instructions.push(OpCode::NOP);
+            self.instructions.push(OpCode::POP_TOP);
+            self.instructions.push(OpCode::JUMP_ABSOLUTE(drain_ip));
+            total.0 += 3;
+            // Replace all break NOPs with JUMP_ABSOLUTE
+            for br in break_points {
+                *self.instructions.get_mut(br).expect("Break wasn't found") =
+                    OpCode::JUMP_ABSOLUTE(drain_ip);
+            }
         }
+        let loop_end = self.instructions.len();
 
-        debug!("BytecodeEmitter::for_loop() ended");
+        // Replace NOPs with FOR_STACK
+        for ip in for_stack_ips {
+            *self
+                .instructions
+                .get_mut(ip)
+                .expect("Instruction wasn't found") = OpCode::FOR_STACK(loop_end - ip);
+        }
+
+        debug!("BytecodeEmitter::list_literal_for_loop() ended");
         total
     }
 
@@ -518,14 +666,29 @@ impl BytecodeEmitter {
             child_symbols.num_deref_vars(),
             child_instructions,
         );
-        let code_object_idx = self.constants_pool.borrow().len();
-        self.constants_pool
-            .borrow_mut()
-            .push(objref!(Object::Code(code_object)));
+        self.compiled_child_symbol_tables += 1;
+        // Functions whose bodies compile to the same bytecode can share one code object
+        let existing_idx = self
+            .constants_pool
+            .borrow()
+            .iter()
+            .position(|c| matches!(&*c.borrow(), Object::Code(co) if *co == code_object));
+        let code_object_idx = match existing_idx {
+            Some(idx) => idx,
+            None => {
+                let mut constants_pool = self.constants_pool.borrow_mut();
+                constants_pool.push(objref!(Object::Code(code_object)));
+                constants_pool.len() - 1
+            }
+        };
 
         // Actual bytecode emission
-        self.instructions
-            .push(OpCode::MAKE_FUNCTION(parameters.len(), code_object_idx));
+        let name_idx = self.const_string(identifier).0;
+        self.instructions.push(OpCode::MAKE_FUNCTION(
+            parameters.len(),
+            code_object_idx,
+            name_idx,
+        ));
         total.0 += 1;
         total += self.emit_store(identifier);
 
@@ -559,23 +722,42 @@ impl BytecodeEmitter {
     }
 
     /// ```
-    /// [if there are accesses
-    ///     LOAD_{LOCAL|DEREF|GLOBAL}
-    ///     *all except last access
-    ///         Access
-    ///         LOAD_ACCESS
-    ///         SWAP_TOP
-    ///         POP_TOP
-    ///     *
-    ///     Last access
+    /// Arguments
+    /// Callee
+    /// CALL_FUNCTION
+    /// ```
+    fn call(
+        &mut self,
+        callee: &MarkedOperationTree,
+        arguments: &[MarkedOperationTree],
+    ) -> Emissions {
+        debug!("BytecodeEmitter::call() started");
+        let mut total = Emissions(0);
+
+        for arg in arguments.iter().rev() {
+            total += self.operation_tree(arg);
+        }
+        total += self.operation_tree(callee);
+        self.instructions
+            .push(OpCode::CALL_FUNCTION(arguments.len()));
+        total.0 += 1;
+
+        debug!("BytecodeEmitter::call() ended");
+        total
+    }
+
+    /// ```
+    /// [if chained
+    ///     Chained assignment
+    /// ][if there are accesses
+    ///     Subscript target
     ///     [if not pure assign
     ///         DUP_TOP
     ///         PUSH_TEMP
     ///         LOAD_ACCESS
     ///         Value
     ///         SWAP_TOP
-    ///         LOAD_ATTR
-    ///         CALL_FUNCTION
+    ///         BINARY_OP
     ///         POP_TEMP
     ///         SWAP_TOP
     ///     ][else
@@ -587,49 +769,35 @@ impl BytecodeEmitter {
     ///     Value
     ///     [if not pure assign
     ///         LOAD_{LOCAL|DEREF|GLOBAL}
-    ///         LOAD_ATTR
-    ///         CALL_FUNCTION
+    ///         BINARY_OP
     ///     ]
     ///     STORE_{LOCAL|DEREF|GLOBAL}
     /// ]
     /// ```
     fn assign_op(
         &mut self,
-        variable: &MarkedString,
-        accesses: &[MarkedOperationTree],
+        target: AssignTarget,
+        chained: &[(MarkedString, Vec<MarkedOperationTree>)],
         asop: &MarkedAsop,
         value: &MarkedOperationTree,
     ) -> Emissions {
         debug!("BytecodeEmitter::assign_op() started");
         let mut total = Emissions(0);
 
-        if accesses.is_empty() {
+        if !chained.is_empty() {
+            total += self.chained_assign(target, chained, value);
+        } else if let AssignTarget::Variable(variable, []) = target {
             total += self.operation_tree(value);
             if !matches!(asop.comp, Asop::Assign) {
                 total += self.emit_load(variable);
                 let op_method_idx =
                     self.const_string(&asop.comp.dunderscore_method().to_string().into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             total += self.emit_store(variable);
         } else {
-            total += self.emit_load(variable);
-            for access in &accesses[..accesses.len() - 1] {
-                total += self.operation_tree(access);
-                self.instructions.push(OpCode::LOAD_ACCESS);
-                total.0 += 1;
-                self.instructions.push(OpCode::SWAP_TOP);
-                total.0 += 1;
-                self.instructions.push(OpCode::POP_TOP);
-                total.0 += 1;
-            }
-            // Unwrap is safe because we already checked that `accesses` is not empty
-            #[allow(clippy::unwrap_used)]
-            let last_access = accesses.last().unwrap();
-            total += self.operation_tree(last_access);
+            total += self.subscript_target(target);
 
             if matches!(asop.comp, Asop::Assign) {
                 total += self.operation_tree(value);
@@ -645,9 +813,7 @@ impl BytecodeEmitter {
                 total.0 += 1;
                 let op_method_idx =
                     self.const_string(&asop.comp.dunderscore_method().to_string().into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
                 self.instructions.push(OpCode::POP_TEMP);
                 total.0 += 1;
@@ -665,6 +831,111 @@ impl BytecodeEmitter {
         total
     }
 
+    /// ```
+    /// Value
+    /// *each target from left to right
+    ///     [if not last target
+    ///         DUP_TOP
+    ///     ]
+    ///     [if there are accesses
+    ///         PUSH_TEMP
+    ///         Subscript target
+    ///         POP_TEMP
+    ///         STORE_ACCESS
+    ///         POP_TOP
+    ///     ][else
+    ///         STORE_{LOCAL|DEREF|GLOBAL}
+    ///     ]
+    /// *
+    /// ```
+    fn chained_assign(
+        &mut self,
+        first: AssignTarget,
+        chained: &[(MarkedString, Vec<MarkedOperationTree>)],
+        value: &MarkedOperationTree,
+    ) -> Emissions {
+        debug!("BytecodeEmitter::chained_assign() started");
+        let mut total = Emissions(0);
+
+        total += self.operation_tree(value);
+        let targets = std::iter::once(first)
+            .chain(
+                chained
+                    .iter()
+                    .map(|(v, a)| AssignTarget::Variable(v, a.as_slice())),
+            )
+            .collect::<Vec<_>>();
+        for (i, &target) in targets.iter().enumerate() {
+            if i < targets.len() - 1 {
+                self.instructions.push(OpCode::DUP_TOP);
+                total.0 += 1;
+            }
+            if let AssignTarget::Variable(variable, []) = target {
+                total += self.emit_store(variable);
+            } else {
+                // The value waits in the temp stack while the container and index are loaded beneath it
+                self.instructions.push(OpCode::PUSH_TEMP);
+                total.0 += 1;
+                total += self.subscript_target(target);
+                self.instructions.push(OpCode::POP_TEMP);
+                total.0 += 1;
+                self.instructions.push(OpCode::STORE_ACCESS);
+                total.0 += 1;
+                self.instructions.push(OpCode::POP_TOP);
+                total.0 += 1;
+            }
+        }
+
+        debug!("BytecodeEmitter::chained_assign() ended");
+        total
+    }
+
+    /// Leaves the innermost container being assigned into and its last access on the stack.
+    ///
+    /// ```
+    /// [if variable
+    ///     LOAD_{LOCAL|DEREF|GLOBAL}
+    ///     *all except last access
+    ///         Access
+    ///         LOAD_ACCESS
+    ///         SWAP_TOP
+    ///         POP_TOP
+    ///     *
+    ///     Last access
+    /// ][else
+    ///     Container
+    ///     Access
+    /// ]
+    /// ```
+    fn subscript_target(&mut self, target: AssignTarget) -> Emissions {
+        let mut total = Emissions(0);
+
+        match target {
+            AssignTarget::Variable(variable, accesses) => {
+                total += self.emit_load(variable);
+                for access in &accesses[..accesses.len() - 1] {
+                    total += self.operation_tree(access);
+                    self.instructions.push(OpCode::LOAD_ACCESS);
+                    total.0 += 1;
+                    self.instructions.push(OpCode::SWAP_TOP);
+                    total.0 += 1;
+                    self.instructions.push(OpCode::POP_TOP);
+                    total.0 += 1;
+                }
+                // Unwrap is safe because callers only pass non-empty `accesses`
+                #[allow(clippy::unwrap_used)]
+                let last_access = accesses.last().unwrap();
+                total += self.operation_tree(last_access);
+            }
+            AssignTarget::Subscript(container, access) => {
+                total += self.operation_tree(container);
+                total += self.operation_tree(access);
+            }
+        }
+
+        total
+    }
+
     fn operation_tree(&mut self, op_tree: &MarkedOperationTree) -> Emissions {
         debug!("BytecodeEmitter::operation_tree() started");
         let mut total = Emissions(0);
@@ -676,7 +947,7 @@ impl BytecodeEmitter {
                     .const_string(&operation.comp.dunderscore_method_unary().to_string().into());
                 self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
                 total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(0));
+                self.instructions.push(OpCode::CALL_FUNCTION(1));
                 total.0 += 1;
             }
             OperationTree::Binary {
@@ -712,6 +983,29 @@ impl BytecodeEmitter {
                         total.0 += 1;
                     }
                 }
+                AstNode::call { callee, arguments } => {
+                    total += self.call(callee, arguments);
+                }
+                AstNode::subscript { value, accesses } => {
+                    total += self.operation_tree(value);
+                    for access in accesses {
+                        total += self.operation_tree(access);
+                        self.instructions.push(OpCode::LOAD_ACCESS);
+                        total.0 += 1;
+
+                        // Remove the original value accessed
+                        self.instructions.push(OpCode::SWAP_TOP);
+                        total.0 += 1;
+                        self.instructions.push(OpCode::POP_TOP);
+                        total.0 += 1;
+                    }
+                }
+                AstNode::named_expr { target, value } => {
+                    total += self.operation_tree(value);
+                    self.instructions.push(OpCode::DUP_TOP);
+                    total.0 += 1;
+                    total += self.emit_store(target);
+                }
                 AstNode::list(list) => {
                     for item in list.iter().rev() {
                         total += self.operation_tree(item);
@@ -755,6 +1049,10 @@ impl BytecodeEmitter {
                     });
                     total.0 += 1;
                 }
+                AstNode::none => {
+                    self.instructions.push(OpCode::LOAD_CONST(0));
+                    total.0 += 1;
+                }
                 non_identity_ast!() => {
                     panic!("Tried calling operation_tree() with {marked_component:?}");
                 }
@@ -772,93 +1070,67 @@ impl BytecodeEmitter {
         match &op.comp {
             Op::Plus => {
                 let op_method_idx = self.const_string(&"__add__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Minus => {
                 let op_method_idx = self.const_string(&"__sub__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Mult => {
                 let op_method_idx = self.const_string(&"__mul__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Div => {
                 let op_method_idx = self.const_string(&"__truediv__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::IntDiv => {
                 let op_method_idx = self.const_string(&"__floordiv__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Mod => {
                 let op_method_idx = self.const_string(&"__mod__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Exp => {
                 let op_method_idx = self.const_string(&"__pow__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Eq => {
                 let op_method_idx = self.const_string(&"__eq__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Neq => {
                 let op_method_idx = self.const_string(&"__eq__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Gt => {
                 let op_method_idx = self.const_string(&"__gt__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Gte => {
                 let op_method_idx = self.const_string(&"__ge__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Lt => {
                 let op_method_idx = self.const_string(&"__lt__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::Lte => {
                 let op_method_idx = self.const_string(&"__le__".into());
-                self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
-                total.0 += 1;
-                self.instructions.push(OpCode::CALL_FUNCTION(2));
+                self.instructions.push(OpCode::BINARY_OP(op_method_idx.0));
                 total.0 += 1;
             }
             Op::And => {
@@ -925,8 +1197,9 @@ impl BytecodeEmitter {
                 total.0 += 1;
             }
             Op::In => {
-                let len = self.instructions.len();
-                self.instructions.swap(len - 1, len - 2);
+                // The container is the one whose `__contains__()` gets called
+                self.instructions.push(OpCode::SWAP_TOP);
+                total.0 += 1;
                 let op_method_idx = self.const_string(&"__contains__".into());
                 self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
                 total.0 += 1;
@@ -934,8 +1207,9 @@ impl BytecodeEmitter {
                 total.0 += 1;
             }
             Op::NotIn => {
-                let len = self.instructions.len();
-                self.instructions.swap(len - 1, len - 2);
+                // The container is the one whose `__contains__()` gets called
+                self.instructions.push(OpCode::SWAP_TOP);
+                total.0 += 1;
                 let op_method_idx = self.const_string(&"__contains__".into());
                 self.instructions.push(OpCode::LOAD_ATTR(op_method_idx.0));
                 total.0 += 1;
@@ -1033,3 +1307,62 @@ impl BytecodeEmitter {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BytecodeEmitter, Object};
+    use crate::parser::Parser;
+
+    fn disassemble(source: &str) -> String {
+        let (parse_results, symbol_table) = Parser::new().parse_from_str(source).unwrap();
+        let mut emitter = BytecodeEmitter::new(symbol_table);
+        emitter.emit(&parse_results.ast_node);
+        format!("{emitter}")
+    }
+
+    #[test]
+    fn test_load_const_number_display() {
+        let bytecode = disassemble("x = 2.5\ny = 2\nz = 2.0\n");
+        let load_consts = bytecode
+            .lines()
+            .filter_map(|l| l.split_once("LOAD_CONST ").map(|(_, c)| c))
+            .collect::<Vec<_>>();
+
+        // Same forms as `print()` would show, with `2.0` being the same constant as `2`
+        assert_eq!(load_consts, vec!["2.5", "2", "2", "None"]);
+    }
+
+    #[test]
+    fn test_identical_functions_share_code_object() {
+        let source = "def outer():\n    def f(a):\n        return a\n    def g(b):\n        return b\n    def h(c):\n        return c + 1\n";
+        let (parse_results, symbol_table) = Parser::new().parse_from_str(source).unwrap();
+        let mut emitter = BytecodeEmitter::new(symbol_table);
+        emitter.emit(&parse_results.ast_node);
+        let (_, _, constants) = emitter.dissolve();
+
+        // `outer`, one shared by `f` and `g`, and `h`
+        let code_objects = constants
+            .unwrap()
+            .iter()
+            .filter(|c| matches!(*c.borrow(), Object::Code(_)))
+            .count();
+        assert_eq!(code_objects, 3);
+    }
+
+    #[test]
+    fn test_for_over_list_literal_skips_generator() {
+        // Any number of items, and the body is only emitted once
+        for source in [
+            "for x in [1, 2, 3]:\n    print(x)\n",
+            "for x in [1, 2, 3, 4, 5, 6, 7, 8, 9]:\n    print(x)\n",
+        ] {
+            let bytecode = disassemble(source);
+            assert!(!bytecode.contains("MAKE_GENERATOR"), "{bytecode}");
+            assert!(!bytecode.contains("BUILD_LIST"), "{bytecode}");
+            assert_eq!(bytecode.matches("STORE_GLOBAL 'x'").count(), 1);
+        }
+
+        // Dynamic iterables keep the generator
+        assert!(disassemble("l = [1]\nfor x in l:\n    print(x)\n").contains("MAKE_GENERATOR"));
+    }
+}
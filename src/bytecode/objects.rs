@@ -1,8 +1,9 @@
+use super::std_lib::BUILTIN_CLASSES;
 use super::vm::RuntimeError;
 use crate::bytecode::{OpCode, VM};
 use crate::util::Map;
 
-use std::cell::RefCell;
+use std::cell::{OnceCell, RefCell};
 use std::rc::Rc;
 
 pub type ObjectRef = Rc<RefCell<Object>>;
@@ -22,17 +23,18 @@ pub enum Object {
     String(String),
     List(Vec<ObjectRef>),
     Set(Vec<ObjectRef>),
-    Dict(Vec<(String, ObjectRef)>),
+    Dict(Vec<(ObjectRef, ObjectRef)>),
     Code(CodeObject),
     Function(CompiledFunction),
     Generator(FrozenGenerator),
+    NotImplemented,
     // TODO: GH-9
     // Class,
 }
 
 impl Object {
     pub fn class_idx(&self) -> usize {
-        // Must be kept updated in VM::start()
+        // Must be kept updated in std_lib::BUILTIN_CLASSES
         match self {
             Object::None => 0,
             Object::Number(_) => 1,
@@ -44,14 +46,15 @@ impl Object {
             Object::Code(_) => 7,
             Object::Function(_) => 8,
             Object::Generator(_) => 9,
+            Object::NotImplemented => 10,
         }
     }
 
-    pub fn class<'vm>(&self, classes: &'vm [Class]) -> &'vm Class {
-        &classes[self.class_idx()]
+    pub fn class<'vm>(&self, classes: &'vm Classes) -> &'vm Class {
+        classes.get(self.class_idx())
     }
 
-    pub fn attr(&self, attr: &str, classes: &[Class]) -> Result<ObjectRef, RuntimeError> {
+    pub fn attr(&self, attr: &str, classes: &Classes) -> Result<ObjectRef, RuntimeError> {
         // TODO: GH-9
         // if let Object::Class(instance) = self {
         //     classes[instance.class()]
@@ -63,9 +66,11 @@ impl Object {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct CodeObject {
     local_vars_num: usize,
+    /// Not needed until closures are executable, but kept for debugging
+    #[allow(dead_code)]
     deref_vars_num: usize,
     bytecode: Vec<OpCode>,
 }
@@ -90,6 +95,7 @@ impl CodeObject {
 
 #[derive(Debug)]
 pub struct CompiledFunction {
+    name: String,
     argc: usize,
     /// Only true for builtin Funcion.__call__() class method
     ignore_argc: bool,
@@ -97,8 +103,9 @@ pub struct CompiledFunction {
 }
 
 impl CompiledFunction {
-    pub fn new(argc: usize, code: FunctionType) -> Self {
+    pub fn new(name: &str, argc: usize, code: FunctionType) -> Self {
         Self {
+            name: name.to_string(),
             argc,
             ignore_argc: false,
             code,
@@ -110,6 +117,10 @@ impl CompiledFunction {
         self
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn ignore_argc(&self) -> bool {
         self.ignore_argc
     }
@@ -238,3 +249,21 @@ impl Class {
         self.attrs.insert(attr.to_string(), val);
     }
 }
+
+/// The builtin classes, indexed by `Object::class_idx()`. Each one is only built the first time it's needed, since a
+/// short script usually touches just a few of them. The gain is negligible: building all of them takes about 13µs in a
+/// release build, around 1% of running a one-line script.
+#[derive(Debug, Default)]
+pub struct Classes([OnceCell<Class>; BUILTIN_CLASSES.len()]);
+
+impl Classes {
+    pub fn get(&self, idx: usize) -> &Class {
+        self.0[idx].get_or_init(BUILTIN_CLASSES[idx])
+    }
+
+    /// Whether the class has been built yet.
+    #[cfg(test)]
+    pub fn is_initialized(&self, idx: usize) -> bool {
+        self.0[idx].get().is_some()
+    }
+}
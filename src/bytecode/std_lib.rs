@@ -1,8 +1,13 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use crate::{
     bytecode::{
         VM,
-        objects::{CompiledFunction, FunctionType, Object, ObjectRef},
-        vm::RuntimeError,
+        objects::{Class, CompiledFunction, FunctionType, Object, ObjectRef},
+        vm::{MAX_CONTAINER_DEPTH, RuntimeError},
     },
     objref,
 };
@@ -14,10 +19,26 @@ pub mod function;
 pub mod generator;
 pub mod list;
 pub mod none;
+pub mod not_implemented;
 pub mod number;
 pub mod set;
 pub mod string;
 
+/// How to build each builtin class, in the order of `Object::class_idx()`.
+pub const BUILTIN_CLASSES: [fn() -> Class; 11] = [
+    none::init_class,
+    number::init_class,
+    boolean::init_class,
+    string::init_class,
+    list::init_class,
+    set::init_class,
+    dict::init_class,
+    code::init_class,
+    function::init_class,
+    generator::init_class,
+    not_implemented::init_class,
+];
+
 #[macro_export]
 macro_rules! class_method {
     ($class:ident, $attr:ident) => {
@@ -25,6 +46,7 @@ macro_rules! class_method {
             stringify!($attr),
             $crate::objref!($crate::bytecode::objects::Object::Function(
                 $crate::bytecode::objects::CompiledFunction::new(
+                    stringify!($attr),
                     0,
                     $crate::bytecode::objects::FunctionType::Rust($attr)
                 )
@@ -37,6 +59,7 @@ macro_rules! class_method {
             stringify!($attr),
             $crate::objref!($crate::bytecode::objects::Object::Function(
                 $crate::bytecode::objects::CompiledFunction::new(
+                    stringify!($attr),
                     $argc,
                     $crate::bytecode::objects::FunctionType::Rust($attr)
                 )
@@ -47,6 +70,7 @@ macro_rules! class_method {
 
 pub fn iter_() -> ObjectRef {
     objref!(Object::Function(CompiledFunction::new(
+        "iter",
         1,
         FunctionType::Rust(iter)
     )))
@@ -61,11 +85,12 @@ pub fn iter(vm: &mut VM) -> Result<(), RuntimeError> {
     })?;
     vm.push_tos(object);
     vm.push_tos(iter);
-    vm.handle_callable_object("__iter__", 1)
+    vm.handle_callable_object(1)
 }
 
 pub fn next_() -> ObjectRef {
     objref!(Object::Function(CompiledFunction::new(
+        "next",
         1,
         FunctionType::Rust(next)
     )))
@@ -83,43 +108,27 @@ pub fn next(vm: &mut VM) -> Result<(), RuntimeError> {
     })?;
     vm.push_tos(object);
     vm.push_tos(next);
-    vm.handle_callable_object("__next__", 1)
+    vm.handle_callable_object(1)
 }
 
 pub fn print_() -> ObjectRef {
     objref!(Object::Function(CompiledFunction::new(
+        "print",
         1,
         FunctionType::Rust(print)
     )))
 }
 pub fn print(vm: &mut VM) -> Result<(), RuntimeError> {
     let object = vm.pop_tos();
-    let object_class = object.borrow().class(vm.classes());
-
-    let str = object_class.attr("__str__");
-    let output = if let Ok(str) = str {
-        vm.push_tos(object);
-        vm.push_tos(str);
-        vm.handle_callable_object("__str__", 1)?;
-        if let Object::String(ref output) = *vm.pop_tos().borrow() {
-            output.clone()
-        } else {
-            return Err(RuntimeError::new("__str__ returned non-string"));
-        }
-    } else {
-        format!(
-            "<{} object at {:p}>",
-            object_class.name(),
-            &*object.borrow()
-        )
-    };
-    println!("{output}");
+    let output = to_str(vm, &object)?;
+    vm.write_output(&format!("{output}\n"))?;
 
     Ok(())
 }
 
 pub fn bool_() -> ObjectRef {
     objref!(Object::Function(CompiledFunction::new(
+        "bool",
         1,
         FunctionType::Rust(bool)
     )))
@@ -131,7 +140,7 @@ pub fn bool(vm: &mut VM) -> Result<(), RuntimeError> {
     if let Ok(bool) = object_class.attr("__bool__") {
         vm.push_tos(object);
         vm.push_tos(bool);
-        vm.handle_callable_object("__bool__", 1)?;
+        vm.handle_callable_object(1)?;
     } else {
         vm.push_tos(objref!(Object::Boolean(true)));
     }
@@ -141,6 +150,7 @@ pub fn bool(vm: &mut VM) -> Result<(), RuntimeError> {
 
 pub fn len_() -> ObjectRef {
     objref!(Object::Function(CompiledFunction::new(
+        "len",
         1,
         FunctionType::Rust(len)
     )))
@@ -152,7 +162,7 @@ pub fn len(vm: &mut VM) -> Result<(), RuntimeError> {
     if let Ok(len) = object_class.attr("__len__") {
         vm.push_tos(object);
         vm.push_tos(len);
-        vm.handle_callable_object("__len__", 1)?;
+        vm.handle_callable_object(1)?;
     } else {
         return Err(RuntimeError::new(&format!(
             "'{}' object has no len()",
@@ -162,3 +172,590 @@ pub fn len(vm: &mut VM) -> Result<(), RuntimeError> {
 
     Ok(())
 }
+
+pub fn time_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "time",
+        0,
+        FunctionType::Rust(time)
+    )))
+}
+pub fn time(vm: &mut VM) -> Result<(), RuntimeError> {
+    // Seconds since the Unix epoch, like Python's `time.time()`
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| RuntimeError::new(&format!("system clock is before the Unix epoch: {e}")))?;
+    vm.push_tos(objref!(Object::Number(now.as_secs_f64())));
+
+    Ok(())
+}
+
+pub fn abs_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "abs",
+        1,
+        FunctionType::Rust(abs)
+    )))
+}
+pub fn abs(vm: &mut VM) -> Result<(), RuntimeError> {
+    let object = vm.pop_tos();
+    let object_class = object.borrow().class(vm.classes());
+
+    let abs = object_class.attr("__abs__").map_err(|_| {
+        RuntimeError::new(&format!(
+            "bad operand type for abs(): '{}'",
+            object_class.name()
+        ))
+    })?;
+    vm.push_tos(object);
+    vm.push_tos(abs);
+    vm.handle_callable_object(1)
+}
+
+pub fn min_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "min",
+        1,
+        FunctionType::Rust(min)
+    )))
+}
+pub fn min(vm: &mut VM) -> Result<(), RuntimeError> {
+    let object = vm.pop_tos();
+    let min = extreme(vm, &object, "__lt__", "min")?;
+    vm.push_tos(min);
+
+    Ok(())
+}
+
+pub fn max_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "max",
+        1,
+        FunctionType::Rust(max)
+    )))
+}
+pub fn max(vm: &mut VM) -> Result<(), RuntimeError> {
+    let object = vm.pop_tos();
+    let max = extreme(vm, &object, "__gt__", "max")?;
+    vm.push_tos(max);
+
+    Ok(())
+}
+
+pub fn sum_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "sum",
+        1,
+        FunctionType::Rust(sum)
+    )))
+}
+/// Adds up the items starting from the first one rather than from `0`, so anything implementing `__add__` can be summed
+/// (e.g. strings get concatenated). An empty collection sums to `0`.
+pub fn sum(vm: &mut VM) -> Result<(), RuntimeError> {
+    let object = vm.pop_tos();
+    let items = collection_items(vm, &object)?;
+
+    let mut items = items.into_iter();
+    let Some(mut total) = items.next() else {
+        vm.push_tos(objref!(Object::Number(0.0)));
+        return Ok(());
+    };
+    for item in items {
+        total = binary_op(vm, &total, "__add__", &item)?;
+    }
+    vm.push_tos(total);
+
+    Ok(())
+}
+
+pub fn sqrt_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "sqrt",
+        1,
+        FunctionType::Rust(sqrt)
+    )))
+}
+pub fn sqrt(vm: &mut VM) -> Result<(), RuntimeError> {
+    let n = number_arg(vm, "sqrt")?;
+    if n < 0.0 {
+        return Err(RuntimeError::new("math domain error"));
+    }
+    vm.push_tos(objref!(Object::Number(n.sqrt())));
+
+    Ok(())
+}
+
+pub fn floor_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "floor",
+        1,
+        FunctionType::Rust(floor)
+    )))
+}
+pub fn floor(vm: &mut VM) -> Result<(), RuntimeError> {
+    let n = number_arg(vm, "floor")?;
+    vm.push_tos(objref!(Object::Number(n.floor())));
+
+    Ok(())
+}
+
+pub fn ceil_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "ceil",
+        1,
+        FunctionType::Rust(ceil)
+    )))
+}
+pub fn ceil(vm: &mut VM) -> Result<(), RuntimeError> {
+    let n = number_arg(vm, "ceil")?;
+    vm.push_tos(objref!(Object::Number(n.ceil())));
+
+    Ok(())
+}
+
+pub fn format_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "format",
+        2,
+        FunctionType::Rust(format)
+    )))
+}
+/// Objects without a `__format__()` can still be formatted with an empty spec, which is the same as `str()`.
+pub fn format(vm: &mut VM) -> Result<(), RuntimeError> {
+    let value = vm.pop_tos();
+    let spec = vm.pop_tos();
+    let Object::String(ref spec_str) = *spec.borrow() else {
+        return Err(RuntimeError::new(&format!(
+            "format() argument 2 must be a string, not '{}'",
+            spec.borrow().class(vm.classes()).name()
+        )));
+    };
+
+    if value.borrow().attr("__format__", vm.classes()).is_ok() {
+        let formatted = binary_method(vm, &value, "__format__", &spec)?;
+        vm.push_tos(formatted);
+    } else if spec_str.is_empty() {
+        let formatted = to_str(vm, &value)?;
+        vm.push_tos(objref!(Object::String(formatted)));
+    } else {
+        return Err(RuntimeError::new(&format!(
+            "unsupported format string passed to {}.__format__",
+            value.borrow().class(vm.classes()).name()
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn deepcopy_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "deepcopy",
+        1,
+        FunctionType::Rust(deepcopy)
+    )))
+}
+pub fn deepcopy(vm: &mut VM) -> Result<(), RuntimeError> {
+    let object = vm.pop_tos();
+    let copy = deep_copy(&object, &mut HashMap::new(), 0)?;
+    vm.push_tos(copy);
+
+    Ok(())
+}
+
+/// Copies `object` and every container inside it. `copies` maps each container already copied to its copy, so that
+/// shared references stay shared in the copy and self-referential containers don't recurse forever.
+fn deep_copy(
+    object: &ObjectRef,
+    copies: &mut HashMap<*const RefCell<Object>, ObjectRef>,
+    depth: usize,
+) -> Result<ObjectRef, RuntimeError> {
+    if let Some(copy) = copies.get(&Rc::as_ptr(object)) {
+        return Ok(copy.clone());
+    }
+
+    let copy = match *object.borrow() {
+        Object::List(_) => objref!(Object::List(Vec::new())),
+        Object::Set(_) => objref!(Object::Set(Vec::new())),
+        Object::Dict(_) => objref!(Object::Dict(Vec::new())),
+        // Nothing else can be changed in place, so it's fine for both structures to share it
+        _ => return Ok(object.clone()),
+    };
+    if depth >= MAX_CONTAINER_DEPTH {
+        return Err(RuntimeError::new(
+            "maximum recursion depth exceeded while copying",
+        ));
+    }
+    // Registered before copying the contents, in case they lead back to `object`
+    copies.insert(Rc::as_ptr(object), copy.clone());
+
+    let contents = match *object.borrow() {
+        Object::List(ref items) => Object::List(
+            items
+                .iter()
+                .map(|i| deep_copy(i, copies, depth + 1))
+                .collect::<Result<_, _>>()?,
+        ),
+        Object::Set(ref items) => Object::Set(
+            items
+                .iter()
+                .map(|i| deep_copy(i, copies, depth + 1))
+                .collect::<Result<_, _>>()?,
+        ),
+        Object::Dict(ref pairs) => Object::Dict(
+            pairs
+                .iter()
+                .map(|(k, v)| Ok((k.clone(), deep_copy(v, copies, depth + 1)?)))
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => unreachable!(),
+    };
+    *copy.borrow_mut() = contents;
+    Ok(copy)
+}
+
+/// What `print()` shows for an object: the result of its `__str__()`, or its class and address if it has none.
+pub fn to_str(vm: &mut VM, object: &ObjectRef) -> Result<String, RuntimeError> {
+    let object_class = object.borrow().class(vm.classes());
+
+    if let Ok(str) = object_class.attr("__str__") {
+        vm.push_tos(object.clone());
+        vm.push_tos(str);
+        vm.handle_callable_object(1)?;
+        if let Object::String(ref output) = *vm.pop_tos().borrow() {
+            Ok(output.clone())
+        } else {
+            Err(RuntimeError::new("__str__ returned non-string"))
+        }
+    } else {
+        Ok(format!(
+            "<{} object at {:p}>",
+            object_class.name(),
+            &*object.borrow()
+        ))
+    }
+}
+
+/// How an object is shown inside a container, which only differs from `to_str()` in that strings are quoted. Shared
+/// by all the container `__str__()` methods so that nested containers format the same way Python's do.
+pub fn repr(vm: &mut VM, object: &ObjectRef) -> Result<String, RuntimeError> {
+    if let Object::String(ref s) = *object.borrow() {
+        return Ok(string::repr(s));
+    }
+    to_str(vm, object)
+}
+
+pub fn getrecursionlimit_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "getrecursionlimit",
+        0,
+        FunctionType::Rust(getrecursionlimit)
+    )))
+}
+pub fn getrecursionlimit(vm: &mut VM) -> Result<(), RuntimeError> {
+    vm.push_tos(objref!(Object::Number(vm.recursion_limit() as f64)));
+
+    Ok(())
+}
+
+pub fn setrecursionlimit_() -> ObjectRef {
+    objref!(Object::Function(CompiledFunction::new(
+        "setrecursionlimit",
+        1,
+        FunctionType::Rust(setrecursionlimit)
+    )))
+}
+pub fn setrecursionlimit(vm: &mut VM) -> Result<(), RuntimeError> {
+    let limit = number_arg(vm, "setrecursionlimit")?;
+    if limit.fract() != 0.0 {
+        return Err(RuntimeError::new("recursion limit must be an integer"));
+    }
+    // Negative limits saturate to 0, which gets rejected below
+    vm.set_recursion_limit(limit as usize)
+}
+
+/// The single argument of a math builtin, which has to be a number.
+fn number_arg(vm: &mut VM, func_name: &str) -> Result<f64, RuntimeError> {
+    let object = vm.pop_tos();
+    let object = object.borrow();
+    match *object {
+        Object::Number(n) => Ok(n),
+        ref other => Err(RuntimeError::new(&format!(
+            "{func_name}() argument must be a number, not '{}'",
+            other.class(vm.classes()).name()
+        ))),
+    }
+}
+
+/// Every item `object` iterates over. Lists and sets are read directly, and anything else is run through its
+/// `__iter__()` generator to the end.
+fn collection_items(vm: &mut VM, object: &ObjectRef) -> Result<Vec<ObjectRef>, RuntimeError> {
+    if let Object::List(items) | Object::Set(items) = &*object.borrow() {
+        return Ok(items.clone());
+    }
+
+    vm.push_tos(object.clone());
+    iter(vm)?;
+    let generator = vm.pop_tos();
+    if !matches!(*generator.borrow(), Object::Generator(_)) {
+        return Err(RuntimeError::new(&format!(
+            "iter() returned non-iterator of type '{}'",
+            generator.borrow().class(vm.classes()).name()
+        )));
+    }
+
+    let mut items = Vec::new();
+    while let Some(item) = vm.resume_generator(&generator)? {
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Where `idx` points in a sequence of `len` items, counting negative indices back from the end. Out of range indices
+/// come out as `len` or more in either direction, for the caller to report. `kind` names the sequence when `idx` isn't
+/// an integer.
+pub fn normalize_index(idx: &Object, len: usize, kind: &str) -> Result<usize, RuntimeError> {
+    match *idx {
+        Object::Number(idx) if idx.is_finite() && idx.trunc() == idx => Ok(if idx < 0.0 {
+            len.wrapping_sub(idx.abs() as usize)
+        } else {
+            idx as usize
+        }),
+        _ => Err(RuntimeError::new(&format!(
+            "{kind} indices must be integers"
+        ))),
+    }
+}
+
+/// The widest width or longest precision a format spec can ask for, so that padding stays a reasonable allocation.
+const MAX_FORMAT_WIDTH: usize = 1 << 20;
+
+/// A parsed format spec, as in `format(x, ">8.2f")`: `[[fill]align][sign][0][width][.precision][type]`. Checking which
+/// parts make sense is left to each `__format__()`.
+#[derive(Debug, Default)]
+pub struct FormatSpec {
+    fill: Option<char>,
+    pub align: Option<char>,
+    pub sign: Option<char>,
+    zero: bool,
+    width: usize,
+    pub precision: Option<usize>,
+    pub ty: Option<char>,
+}
+
+impl FormatSpec {
+    pub fn parse(spec: &str) -> Result<Self, RuntimeError> {
+        let chars = spec.chars().collect::<Vec<_>>();
+        let is_align = |c: &char| matches!(c, '<' | '>' | '^' | '=');
+        // `None` if there are no digits at all
+        let digits = |i: &mut usize| -> Result<Option<usize>, RuntimeError> {
+            let start = *i;
+            while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+                *i += 1;
+            }
+            if start == *i {
+                return Ok(None);
+            }
+            chars[start..*i]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .ok()
+                .filter(|&n| n <= MAX_FORMAT_WIDTH)
+                .map(Some)
+                .ok_or(RuntimeError::new(
+                    "too many decimal digits in format string",
+                ))
+        };
+
+        let mut parsed = Self::default();
+        let mut i = 0;
+        if chars.get(1).is_some_and(is_align) {
+            parsed.fill = Some(chars[0]);
+            parsed.align = Some(chars[1]);
+            i = 2;
+        } else if chars.first().is_some_and(is_align) {
+            parsed.align = Some(chars[0]);
+            i = 1;
+        }
+        if let Some(&sign @ ('+' | '-' | ' ')) = chars.get(i) {
+            parsed.sign = Some(sign);
+            i += 1;
+        }
+        if chars.get(i) == Some(&'0') {
+            parsed.zero = true;
+            i += 1;
+        }
+        parsed.width = digits(&mut i)?.unwrap_or(0);
+        if chars.get(i) == Some(&'.') {
+            i += 1;
+            parsed.precision = Some(
+                digits(&mut i)?.ok_or(RuntimeError::new("format specifier missing precision"))?,
+            );
+        }
+        parsed.ty = chars.get(i).copied();
+        if i + usize::from(parsed.ty.is_some()) < chars.len() {
+            return Err(RuntimeError::new("invalid format specifier"));
+        }
+
+        Ok(parsed)
+    }
+
+    /// Pads `sign` followed by `body` out to the spec's width. Numbers align right by default, and zero padding goes
+    /// between their sign and digits, while everything else aligns left.
+    pub fn pad(&self, sign: &str, body: &str, numeric: bool) -> String {
+        let fill = self.fill.unwrap_or(if self.zero { '0' } else { ' ' });
+        let align = self.align.unwrap_or(match (numeric, self.zero) {
+            (true, true) => '=',
+            (true, false) => '>',
+            (false, _) => '<',
+        });
+        let padding = self
+            .width
+            .saturating_sub(sign.chars().count() + body.chars().count());
+        let fill = |n: usize| fill.to_string().repeat(n);
+
+        match align {
+            '<' => format!("{sign}{body}{}", fill(padding)),
+            '^' => format!(
+                "{}{sign}{body}{}",
+                fill(padding / 2),
+                fill(padding - padding / 2)
+            ),
+            '=' => format!("{sign}{}{body}", fill(padding)),
+            _ => format!("{}{sign}{body}", fill(padding)),
+        }
+    }
+}
+
+/// How many times `sequence * count` repeats a string or list. Like Python, zero or a negative count repeats it no
+/// times at all. `None` if `count` isn't a number, so the sequence can return `NotImplemented`.
+pub fn repeat_count(count: &Object) -> Result<Option<usize>, RuntimeError> {
+    let Object::Number(count) = *count else {
+        return Ok(None);
+    };
+    if !count.is_finite() || count.trunc() != count {
+        return Err(RuntimeError::new(
+            "can't multiply sequence by a non-integer number",
+        ));
+    }
+
+    Ok(Some(count.max(0.0) as usize))
+}
+
+/// The most items (or string bytes) `sequence * count` is allowed to build, well before allocating them would fail.
+const MAX_REPEATED_LEN: usize = 1 << 26;
+
+/// The length of a sequence of `len` repeated `count` times, if it isn't unreasonably long.
+pub fn repeated_len(len: usize, count: usize) -> Result<usize, RuntimeError> {
+    len.checked_mul(count)
+        .filter(|&total| total <= MAX_REPEATED_LEN)
+        .ok_or(RuntimeError::new("repeated sequence is too long"))
+}
+
+/// The item that wins every `item.<method>(best)` comparison, used by `min()` and `max()`.
+fn extreme(
+    vm: &mut VM,
+    object: &ObjectRef,
+    method: &str,
+    func_name: &str,
+) -> Result<ObjectRef, RuntimeError> {
+    let items = collection_items(vm, object)?;
+
+    let mut items = items.into_iter();
+    let mut best = items
+        .next()
+        .ok_or_else(|| RuntimeError::new(&format!("{func_name}() arg is an empty sequence")))?;
+    for item in items {
+        if let Object::Boolean(true) = *binary_op(vm, &item, method, &best)?.borrow() {
+            best = item;
+        }
+    }
+
+    Ok(best)
+}
+
+/// Each binary operator method with its reflected counterpart and the operator itself, as shown in errors.
+const BINARY_OPERATORS: [(&str, &str, &str); 12] = [
+    ("__add__", "__radd__", "+"),
+    ("__sub__", "__rsub__", "-"),
+    ("__mul__", "__rmul__", "*"),
+    ("__truediv__", "__rtruediv__", "/"),
+    ("__floordiv__", "__rfloordiv__", "//"),
+    ("__mod__", "__rmod__", "%"),
+    ("__pow__", "__rpow__", "**"),
+    ("__eq__", "__eq__", "=="),
+    ("__lt__", "__gt__", "<"),
+    ("__le__", "__ge__", "<="),
+    ("__gt__", "__lt__", ">"),
+    ("__ge__", "__le__", ">="),
+];
+
+/// `left <op> right` the way Python dispatches it: `left.<method>(right)` first, then `right.<reflected>(left)` if the
+/// operands are of different types and the first is missing or returns `NotImplemented`. When neither side supports the
+/// operation, `==` falls back to identity and everything else is an error.
+pub fn binary_op(
+    vm: &mut VM,
+    left: &ObjectRef,
+    method: &str,
+    right: &ObjectRef,
+) -> Result<ObjectRef, RuntimeError> {
+    let Some(&(_, reflected, symbol)) = BINARY_OPERATORS.iter().find(|(m, _, _)| *m == method)
+    else {
+        return binary_method(vm, left, method, right);
+    };
+
+    if left.borrow().attr(method, vm.classes()).is_ok() {
+        let result = binary_method(vm, left, method, right)?;
+        if !matches!(*result.borrow(), Object::NotImplemented) {
+            return Ok(result);
+        }
+    }
+    let same_class = left.borrow().class_idx() == right.borrow().class_idx();
+    if !same_class && right.borrow().attr(reflected, vm.classes()).is_ok() {
+        let result = binary_method(vm, right, reflected, left)?;
+        if !matches!(*result.borrow(), Object::NotImplemented) {
+            return Ok(result);
+        }
+    }
+
+    let left_class = left.borrow().class(vm.classes()).name().to_string();
+    let right_class = right.borrow().class(vm.classes()).name().to_string();
+    match method {
+        "__eq__" => Ok(objref!(Object::Boolean(Rc::ptr_eq(left, right)))),
+        "__lt__" | "__le__" | "__gt__" | "__ge__" => Err(RuntimeError::new(&format!(
+            "'{symbol}' not supported between instances of '{left_class}' and '{right_class}'"
+        ))),
+        _ => Err(RuntimeError::new(&format!(
+            "unsupported operand type(s) for {symbol}: '{left_class}' and '{right_class}'"
+        ))),
+    }
+}
+
+/// Calls `left.<method>(right)` and returns the result.
+pub fn binary_method(
+    vm: &mut VM,
+    left: &ObjectRef,
+    method: &str,
+    right: &ObjectRef,
+) -> Result<ObjectRef, RuntimeError> {
+    let left_method = left.borrow().attr(method, vm.classes())?;
+    vm.push_tos(right.clone());
+    vm.push_tos(left.clone());
+    vm.push_tos(left_method);
+    vm.handle_callable_object(2)?;
+
+    Ok(vm.pop_tos())
+}
+
+/// `left == right` through `binary_op()`, short-circuiting when both are the same object.
+pub fn eq(vm: &mut VM, left: &ObjectRef, right: &ObjectRef) -> Result<bool, RuntimeError> {
+    if Rc::ptr_eq(left, right) {
+        return Ok(true);
+    }
+
+    if let Object::Boolean(res) = *binary_op(vm, left, "__eq__", right)?.borrow() {
+        Ok(res)
+    } else {
+        Err(RuntimeError::new("__eq__ returned non-boolean"))
+    }
+}
@@ -47,10 +47,8 @@ fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Boolean(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Boolean' == '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf == other)));
@@ -77,10 +75,8 @@ fn __lt__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Boolean(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Boolean' < '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(!slf && other)));
@@ -96,10 +92,8 @@ fn __le__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Boolean(_other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Boolean' <= '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(!slf)));
@@ -115,10 +109,8 @@ fn __gt__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Boolean(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Boolean' > '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf && !other)));
@@ -134,10 +126,8 @@ fn __ge__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Boolean(_other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Boolean' >= '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf)));
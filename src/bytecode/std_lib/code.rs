@@ -33,10 +33,8 @@ fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Code(_) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Code' == '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(Rc::ptr_eq(&slf_, &other_))));
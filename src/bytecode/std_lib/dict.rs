@@ -1,6 +1,6 @@
-use super::super::objects::{Class, Object};
+use super::super::objects::{Class, Object, ObjectRef};
 use super::super::vm::RuntimeError;
-use crate::bytecode::VM;
+use crate::bytecode::{VM, std_lib};
 use crate::{class_method, objref};
 
 pub fn init_class() -> Class {
@@ -33,35 +33,25 @@ fn __str__(vm: &mut VM) -> Result<(), RuntimeError> {
         panic!();
     };
 
-    let mut display = String::new();
-    for (i, (k, v)) in slf.iter().enumerate() {
-        // Try to call the value's __str__() method as well
-        let v_class = v.borrow().class(vm.classes()).name();
-        let v_display = if let Ok(v_str) = v.borrow().attr("__str__", vm.classes()) {
-            vm.push_tos(v.clone());
-            vm.push_tos(v_str);
-            vm.handle_callable_object("__str__", 1)?;
-            let v_display_ = vm.pop_tos();
-            if let Object::String(ref v_display) = *v_display_.borrow() {
-                v_display.clone()
-            } else {
-                return Err(RuntimeError::new("__str__ returned non-string"));
-            }
-        } else {
-            format!("<{v_class} object at {:p}>", &*v.borrow())
-        };
-        if matches!(*v.borrow(), Object::String(_)) {
-            display.push_str(&format!("'{k}': '{v_display}'"));
-        } else {
-            display.push_str(&format!("'{k}': {v_display}"));
-        }
-
-        // Only add a comma separation if there are more key-value pairs to output
-        if i < slf.len() - 1 {
-            display.push_str(", ");
-        }
+    if !vm.enter_repr(&slf_) {
+        vm.push_tos(objref!(Object::String("{...}".to_string())));
+        return Ok(());
     }
-    vm.push_tos(objref!(Object::String(format!("{{{display}}}"))));
+    let items = slf
+        .iter()
+        .map(|(k, v)| {
+            Ok(format!(
+                "{}: {}",
+                std_lib::repr(vm, k)?,
+                std_lib::repr(vm, v)?
+            ))
+        })
+        .collect::<Result<Vec<_>, RuntimeError>>();
+    vm.exit_repr();
+    vm.push_tos(objref!(Object::String(format!(
+        "{{{}}}",
+        items?.join(", ")
+    ))));
 
     Ok(())
 }
@@ -75,40 +65,64 @@ fn __len__(vm: &mut VM) -> Result<(), RuntimeError> {
     Ok(())
 }
 
-fn __getitem__(vm: &mut VM) -> Result<(), RuntimeError> {
-    let slf_ = vm.pop_tos();
+/// Where `key` is among the keys of `slf_`, comparing them with `__eq__()` since there's no hashing yet.
+fn find_key(vm: &mut VM, slf_: &ObjectRef, key: &ObjectRef) -> Result<Option<usize>, RuntimeError> {
+    // Mutable containers could change after being stored, and then never be found again
+    if let Object::List(_) | Object::Set(_) | Object::Dict(_) = *key.borrow() {
+        return Err(RuntimeError::new(&format!(
+            "unhashable type: '{}'",
+            key.borrow().class(vm.classes()).name()
+        )));
+    }
+
+    // Cloned so that no borrow of the dict is held while `__eq__()` runs
     let Object::Dict(ref slf) = *slf_.borrow() else {
         panic!();
     };
+    let keys = slf.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+    for (idx, k) in keys.iter().enumerate() {
+        if std_lib::eq(vm, k, key)? {
+            return Ok(Some(idx));
+        }
+    }
 
-    let key_ = vm.pop_tos();
-    let Object::String(ref key) = *key_.borrow() else {
-        return Err(RuntimeError::new("dict keys must be strings"));
-    };
-    if let Some((_, item)) = slf.iter().find(|(k, _)| k == key) {
-        vm.push_tos(item.clone());
-    } else {
-        return Err(RuntimeError::new(&format!("key '{key}' not found in dict")));
+    Ok(None)
+}
+
+fn key_error(vm: &mut VM, key: &ObjectRef) -> RuntimeError {
+    match std_lib::repr(vm, key) {
+        Ok(key) => RuntimeError::new(&format!("key {key} not found in dict")),
+        Err(e) => e,
     }
+}
+
+fn __getitem__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let key = vm.pop_tos();
+
+    let Some(idx) = find_key(vm, &slf_, &key)? else {
+        return Err(key_error(vm, &key));
+    };
+    let Object::Dict(ref slf) = *slf_.borrow() else {
+        panic!();
+    };
+    vm.push_tos(slf[idx].1.clone());
 
     Ok(())
 }
 
 fn __setitem__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
+    let key = vm.pop_tos();
+    let new_val = vm.pop_tos();
+
+    let idx = find_key(vm, &slf_, &key)?;
     let Object::Dict(ref mut slf) = *slf_.borrow_mut() else {
         panic!();
     };
-
-    let key_ = vm.pop_tos();
-    let Object::String(ref key) = *key_.borrow() else {
-        return Err(RuntimeError::new("dict keys must be strings"));
-    };
-    if let Some(idx) = slf.iter_mut().position(|(k, _)| k == key) {
-        let new_val = vm.pop_tos();
-        slf[idx].1 = new_val;
-    } else {
-        return Err(RuntimeError::new(&format!("key '{key}' not found in dict")));
+    match idx {
+        Some(idx) => slf[idx].1 = new_val,
+        None => slf.push((key, new_val)),
     }
 
     Ok(())
@@ -116,36 +130,25 @@ fn __setitem__(vm: &mut VM) -> Result<(), RuntimeError> {
 
 fn __delitem__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
+    let key = vm.pop_tos();
+
+    let Some(idx) = find_key(vm, &slf_, &key)? else {
+        return Err(key_error(vm, &key));
+    };
     let Object::Dict(ref mut slf) = *slf_.borrow_mut() else {
         panic!();
     };
-
-    let key_ = vm.pop_tos();
-    let Object::String(ref key) = *key_.borrow() else {
-        return Err(RuntimeError::new("dict keys must be strings"));
-    };
-    if let Some(idx) = slf.iter().position(|(k, _)| k == key) {
-        slf.remove(idx);
-    } else {
-        return Err(RuntimeError::new(&format!("key '{key}' not found in dict")));
-    }
+    slf.remove(idx);
 
     Ok(())
 }
 
 fn __contains__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
-    let Object::Dict(ref slf) = *slf_.borrow() else {
-        panic!();
-    };
+    let key = vm.pop_tos();
 
-    let key_ = vm.pop_tos();
-    let Object::String(ref key) = *key_.borrow() else {
-        return Err(RuntimeError::new("dict keys must be strings"));
-    };
-    vm.push_tos(objref!(Object::Boolean(
-        slf.iter().find(|(k, _)| k == key).is_some()
-    )));
+    let found = find_key(vm, &slf_, &key)?.is_some();
+    vm.push_tos(objref!(Object::Boolean(found)));
 
     Ok(())
 }
@@ -157,14 +160,12 @@ fn __iter__(vm: &mut VM) -> Result<(), RuntimeError> {
     };
 
     let key_list = objref!(Object::List(
-        slf.iter()
-            .map(|(k, _)| objref!(Object::String(k.clone())))
-            .collect::<Vec<_>>()
+        slf.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>()
     ));
     let list_iter = key_list.borrow().attr("__iter__", vm.classes())?;
     vm.push_tos(key_list);
     vm.push_tos(list_iter);
-    vm.handle_callable_object("__iter__", 1)?;
+    vm.handle_callable_object(1)?;
 
     Ok(())
 }
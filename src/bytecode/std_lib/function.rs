@@ -24,7 +24,7 @@ fn __call__(vm: &mut VM) -> Result<(), RuntimeError> {
     let Object::Function(ref slf) = *slf_.borrow() else {
         panic!();
     };
-    vm.execute_function("__call__", slf, slf.argc())?;
+    vm.execute_function(slf, slf.argc())?;
 
     Ok(())
 }
@@ -44,10 +44,8 @@ fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Function(_) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Function' == '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(Rc::ptr_eq(&slf_, &other_))));
@@ -2,6 +2,7 @@ use crate::{
     bytecode::{
         VM,
         objects::{Class, Object},
+        std_lib,
         vm::RuntimeError,
     },
     class_method, objref,
@@ -13,6 +14,7 @@ pub fn init_class() -> Class {
     class_method!(class, __bool__, 1);
     class_method!(class, __iter__, 1);
     class_method!(class, __next__, 1);
+    class_method!(class, __contains__, 2);
 
     class
 }
@@ -42,3 +44,18 @@ fn __next__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     Ok(())
 }
+
+/// Only runs the generator until the first match, leaving the rest of it for later like Python does.
+fn __contains__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let val = vm.pop_tos();
+    while let Some(item) = vm.resume_generator(&slf_)? {
+        if std_lib::eq(vm, &val, &item)? {
+            vm.push_tos(objref!(Object::Boolean(true)));
+            return Ok(());
+        }
+    }
+    vm.push_tos(objref!(Object::Boolean(false)));
+
+    Ok(())
+}
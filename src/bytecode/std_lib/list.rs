@@ -1,7 +1,9 @@
+use std::rc::Rc;
+
 use super::super::objects::{Class, Object};
 use super::super::vm::RuntimeError;
 use crate::bytecode::objects::FrozenGenerator;
-use crate::bytecode::{OpCode, VM};
+use crate::bytecode::{OpCode, VM, std_lib};
 use crate::{class_method, objref};
 
 pub fn init_class() -> Class {
@@ -15,6 +17,9 @@ pub fn init_class() -> Class {
     class_method!(class, __setitem__, 3);
     class_method!(class, __delitem__, 2);
     class_method!(class, __contains__, 2);
+    class_method!(class, __eq__, 2);
+    class_method!(class, __mul__, 2);
+    class_method!(class, __rmul__, 2);
 
     class
 }
@@ -35,35 +40,16 @@ fn __str__(vm: &mut VM) -> Result<(), RuntimeError> {
         panic!();
     };
 
-    let mut display = String::new();
-    for (i, v) in slf.iter().enumerate() {
-        // Try to call the value's __str__() method as well
-        let v_class = v.borrow().class(vm.classes()).name();
-        let v_display = if let Ok(v_str) = v.borrow().attr("__str__", vm.classes()) {
-            vm.push_tos(v.clone());
-            vm.push_tos(v_str);
-            vm.handle_callable_object("__str__", 1)?;
-            let v_display_ = vm.pop_tos();
-            if let Object::String(ref v_display) = *v_display_.borrow() {
-                v_display.clone()
-            } else {
-                return Err(RuntimeError::new("__str__ returned non-string"));
-            }
-        } else {
-            format!("<{v_class} object at {:p}>", &*v.borrow())
-        };
-        if matches!(*v.borrow(), Object::String(_)) {
-            display.push_str(&format!("'{v_display}'"));
-        } else {
-            display.push_str(&v_display);
-        }
-
-        // Only add a comma separation if there are more key-value pairs to output
-        if i < slf.len() - 1 {
-            display.push_str(", ");
-        }
+    if !vm.enter_repr(&slf_) {
+        vm.push_tos(objref!(Object::String("[...]".to_string())));
+        return Ok(());
     }
-    vm.push_tos(objref!(Object::String(format!("[{display}]"))));
+    let items = slf
+        .iter()
+        .map(|v| std_lib::repr(vm, v))
+        .collect::<Result<Vec<_>, _>>();
+    vm.exit_repr();
+    vm.push_tos(objref!(Object::String(format!("[{}]", items?.join(", ")))));
 
     Ok(())
 }
@@ -74,19 +60,7 @@ fn __getitem__(vm: &mut VM) -> Result<(), RuntimeError> {
         panic!();
     };
 
-    let idx_ = vm.pop_tos();
-    let Object::Number(idx) = *idx_.borrow() else {
-        return Err(RuntimeError::new("list indices must be integers"));
-    };
-    let idx = if idx.is_finite() && idx.trunc() == idx {
-        if idx.is_sign_negative() {
-            slf.len().wrapping_sub(idx.trunc().abs() as usize)
-        } else {
-            idx.trunc() as usize
-        }
-    } else {
-        return Err(RuntimeError::new("list indices must be integers"));
-    };
+    let idx = std_lib::normalize_index(&vm.pop_tos().borrow(), slf.len(), "list")?;
 
     vm.push_tos(
         slf.get(idx)
@@ -133,7 +107,7 @@ fn __iter__(vm: &mut VM) -> Result<(), RuntimeError> {
                 OpCode::DUP_TOP, // Duplicate for use in LOAD_ACCESS
                 OpCode::LOAD_LOCAL(3),
                 OpCode::LOAD_LOCAL(5),
-                OpCode::CALL_FUNCTION(3),
+                OpCode::CALL_FUNCTION(2),
                 OpCode::JUMP_IF_TRUE(11), // until index == len
                 OpCode::LOAD_ACCESS,
                 OpCode::SWAP_TOP,
@@ -174,19 +148,7 @@ fn __setitem__(vm: &mut VM) -> Result<(), RuntimeError> {
         panic!();
     };
 
-    let idx_ = vm.pop_tos();
-    let Object::Number(idx) = *idx_.borrow() else {
-        return Err(RuntimeError::new("list indices must be integers"));
-    };
-    let idx = if idx.is_finite() && idx.trunc() == idx {
-        if idx.is_sign_negative() {
-            slf.len().wrapping_sub(idx.trunc().abs() as usize)
-        } else {
-            idx.trunc() as usize
-        }
-    } else {
-        return Err(RuntimeError::new("list indices must be integers"));
-    };
+    let idx = std_lib::normalize_index(&vm.pop_tos().borrow(), slf.len(), "list")?;
 
     if idx < slf.len() {
         let new_val = vm.pop_tos();
@@ -204,19 +166,7 @@ fn __delitem__(vm: &mut VM) -> Result<(), RuntimeError> {
         panic!();
     };
 
-    let idx_ = vm.pop_tos();
-    let Object::Number(idx) = *idx_.borrow() else {
-        return Err(RuntimeError::new("list indices must be integers"));
-    };
-    let idx = if idx.is_finite() && idx.trunc() == idx {
-        if idx.is_sign_negative() {
-            slf.len().wrapping_sub(idx.trunc().abs() as usize)
-        } else {
-            idx.trunc() as usize
-        }
-    } else {
-        return Err(RuntimeError::new("list indices must be integers"));
-    };
+    let idx = std_lib::normalize_index(&vm.pop_tos().borrow(), slf.len(), "list")?;
 
     if idx < slf.len() {
         slf.remove(idx);
@@ -229,33 +179,78 @@ fn __delitem__(vm: &mut VM) -> Result<(), RuntimeError> {
 
 fn __contains__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
-    let Object::List(ref slf) = *slf_.borrow_mut() else {
+    // Cloned so that comparing against the list itself doesn't need to borrow it again
+    let Object::List(ref slf) = *slf_.borrow() else {
         panic!();
     };
+    let slf = slf.clone();
 
     let val = vm.pop_tos();
-    let Ok(val_eq) = val.borrow().attr("__eq__", vm.classes()) else {
-        vm.push_tos(objref!(Object::Boolean(false)));
+    for item in &slf {
+        if std_lib::eq(vm, &val, item)? {
+            vm.push_tos(objref!(Object::Boolean(true)));
+            return Ok(());
+        }
+    }
+    vm.push_tos(objref!(Object::Boolean(false)));
+
+    Ok(())
+}
+
+fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let other_ = vm.pop_tos();
+    if Rc::ptr_eq(&slf_, &other_) {
+        vm.push_tos(objref!(Object::Boolean(true)));
         return Ok(());
+    }
+
+    // Cloned so that items can be compared without keeping either list borrowed
+    let (slf, other) = match (&*slf_.borrow(), &*other_.borrow()) {
+        (Object::List(slf), Object::List(other)) if slf.len() == other.len() => {
+            (slf.clone(), other.clone())
+        }
+        _ => {
+            vm.push_tos(objref!(Object::Boolean(false)));
+            return Ok(());
+        }
     };
 
-    for item in slf {
-        vm.push_tos(item.clone());
-        vm.push_tos(val.clone());
-        vm.push_tos(val_eq.clone());
-        if vm.handle_callable_object("__eq__", 2).is_ok() {
-            let eq_res_ = vm.pop_tos();
-            let Object::Boolean(eq_res) = *eq_res_.borrow() else {
-                continue;
-            };
-
-            if eq_res {
-                vm.push_tos(eq_res_);
-                return Ok(());
-            }
+    vm.enter_comparison()?;
+    let mut result = Ok(true);
+    for (slf_item, other_item) in slf.iter().zip(&other) {
+        result = std_lib::eq(vm, slf_item, other_item);
+        if !matches!(result, Ok(true)) {
+            break;
         }
     }
-    vm.push_tos(objref!(Object::Boolean(false)));
+    vm.exit_comparison();
+
+    vm.push_tos(objref!(Object::Boolean(result?)));
 
     Ok(())
 }
+
+fn __mul__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let Object::List(ref slf) = *slf_.borrow() else {
+        panic!();
+    };
+
+    let other_ = vm.pop_tos();
+    let Some(count) = std_lib::repeat_count(&other_.borrow())? else {
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
+    };
+
+    let len = std_lib::repeated_len(slf.len(), count)?;
+    vm.push_tos(objref!(Object::List(
+        slf.iter().cycle().take(len).cloned().collect()
+    )));
+
+    Ok(())
+}
+
+fn __rmul__(vm: &mut VM) -> Result<(), RuntimeError> {
+    __mul__(vm)
+}
@@ -36,10 +36,8 @@ fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::None = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'NoneType' == '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(true)));
@@ -0,0 +1,23 @@
+use crate::{
+    bytecode::{
+        VM,
+        objects::{Class, Object},
+        vm::RuntimeError,
+    },
+    class_method, objref,
+};
+
+pub fn init_class() -> Class {
+    let mut class = Class::new("NotImplementedType");
+
+    class_method!(class, __str__, 1);
+
+    class
+}
+
+fn __str__(vm: &mut VM) -> Result<(), RuntimeError> {
+    vm.pop_tos();
+    vm.push_tos(objref!(Object::String("NotImplemented".to_string())));
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use super::super::objects::{Class, Object};
 use super::super::vm::RuntimeError;
 use crate::bytecode::VM;
+use crate::bytecode::std_lib::FormatSpec;
 use crate::{class_method, objref};
 
 pub fn init_class() -> Class {
@@ -8,6 +9,7 @@ pub fn init_class() -> Class {
 
     class_method!(class, __bool__, 1);
     class_method!(class, __str__, 1);
+    class_method!(class, __format__, 2);
     class_method!(class, __add__, 2);
     class_method!(class, __sub__, 2);
     class_method!(class, __mul__, 2);
@@ -16,6 +18,7 @@ pub fn init_class() -> Class {
     class_method!(class, __floordiv__, 2);
     class_method!(class, __pow__, 2);
     class_method!(class, __neg__, 1);
+    class_method!(class, __abs__, 1);
     class_method!(class, __eq__, 2);
     class_method!(class, __lt__, 2);
     class_method!(class, __le__, 2);
@@ -25,6 +28,18 @@ pub fn init_class() -> Class {
     class
 }
 
+/// How a number is shown to the user: integral values without a fractional part (`2`), anything else in its shortest
+/// form (`2.5`). Shared with the disassembler so both always agree.
+pub fn display(n: f64) -> String {
+    if n.is_nan() {
+        "nan".to_string()
+    } else if n.is_infinite() {
+        (if n.is_sign_negative() { "-inf" } else { "inf" }).to_string()
+    } else {
+        n.to_string()
+    }
+}
+
 fn __bool__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
     let Object::Number(slf) = *slf_.borrow() else {
@@ -39,7 +54,57 @@ fn __str__(vm: &mut VM) -> Result<(), RuntimeError> {
     let Object::Number(slf) = *slf_.borrow() else {
         panic!();
     };
-    vm.push_tos(objref!(Object::String(slf.to_string())));
+    vm.push_tos(objref!(Object::String(display(slf))));
+
+    Ok(())
+}
+
+fn __format__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let Object::Number(slf) = *slf_.borrow() else {
+        panic!();
+    };
+    let spec_ = vm.pop_tos();
+    let Object::String(ref spec) = *spec_.borrow() else {
+        return Err(RuntimeError::new("format spec must be a string"));
+    };
+    let spec = FormatSpec::parse(spec)?;
+
+    let unknown_code = |code: char| {
+        RuntimeError::new(&format!(
+            "unknown format code '{code}' for object of type 'Number'"
+        ))
+    };
+    let body = match (spec.ty, spec.precision) {
+        (None, None) => display(slf.abs()),
+        (None, Some(_)) => {
+            return Err(RuntimeError::new(
+                "a precision needs a format code, like 'f'",
+            ));
+        }
+        (Some('d'), Some(_)) => {
+            return Err(RuntimeError::new(
+                "precision not allowed in integer format specifier",
+            ));
+        }
+        (Some('d'), None) if slf.fract() == 0.0 => format!("{:.0}", slf.abs()),
+        (Some('f'), precision) => format!("{:.*}", precision.unwrap_or(6), slf.abs()),
+        (Some('F'), precision) => {
+            format!("{:.*}", precision.unwrap_or(6), slf.abs()).to_uppercase()
+        }
+        (Some('%'), precision) => format!("{:.*}%", precision.unwrap_or(6), slf.abs() * 100.0),
+        (Some(code), _) => return Err(unknown_code(code)),
+    };
+    let sign = if slf.is_sign_negative() {
+        "-"
+    } else {
+        match spec.sign {
+            Some('+') => "+",
+            Some(' ') => " ",
+            _ => "",
+        }
+    };
+    vm.push_tos(objref!(Object::String(spec.pad(sign, &body, true))));
 
     Ok(())
 }
@@ -52,10 +117,8 @@ fn __add__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' + '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Number(slf + other)));
@@ -71,10 +134,8 @@ fn __sub__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' - '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Number(slf - other)));
@@ -90,10 +151,8 @@ fn __mul__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' * '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Number(slf * other)));
@@ -109,10 +168,8 @@ fn __truediv__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' / '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Number(slf / other)));
@@ -128,10 +185,8 @@ fn __mod__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' % '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Number(slf % other)));
@@ -147,10 +202,8 @@ fn __floordiv__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' // '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Number((slf / other).floor())));
@@ -166,10 +219,8 @@ fn __pow__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' ** '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Number(slf.powf(other))));
@@ -188,6 +239,18 @@ fn __neg__(vm: &mut VM) -> Result<(), RuntimeError> {
     Ok(())
 }
 
+fn __abs__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let Object::Number(slf) = *slf_.borrow() else {
+        panic!();
+    };
+    vm.push_tos(objref!(Object::Number(slf.abs())));
+
+    Ok(())
+}
+
+/// IEEE 754 equality, so `0 == -0` even though their signs differ. A boolean compares as the number it stands for,
+/// which also covers `True == 1` through the reflected call from `Boolean.__eq__()`.
 fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
     let Object::Number(slf) = *slf_.borrow() else {
@@ -195,11 +258,13 @@ fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
     };
 
     let other_ = vm.pop_tos();
-    let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' == '{other_class}'` is not a supported operation"
-        )));
+    let other = match *other_.borrow() {
+        Object::Number(other) => other,
+        Object::Boolean(other) => f64::from(u8::from(other)),
+        _ => {
+            vm.push_tos(objref!(Object::NotImplemented));
+            return Ok(());
+        }
     };
 
     vm.push_tos(objref!(Object::Boolean(slf == other)));
@@ -215,10 +280,8 @@ fn __lt__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' < '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf < other)));
@@ -234,10 +297,8 @@ fn __le__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' <= '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf <= other)));
@@ -253,10 +314,8 @@ fn __gt__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' > '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf > other)));
@@ -272,10 +331,8 @@ fn __ge__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::Number(other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'Number' >= '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf >= other)));
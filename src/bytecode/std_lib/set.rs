@@ -1,6 +1,6 @@
 use super::super::objects::{Class, Object};
 use super::super::vm::RuntimeError;
-use crate::bytecode::VM;
+use crate::bytecode::{VM, std_lib};
 use crate::{class_method, objref};
 
 pub fn init_class() -> Class {
@@ -31,35 +31,25 @@ fn __str__(vm: &mut VM) -> Result<(), RuntimeError> {
         panic!();
     };
 
-    let mut display = String::new();
-    for (i, v) in slf.iter().enumerate() {
-        // Try to call the value's __str__() method as well
-        let v_class = v.borrow().class(vm.classes()).name();
-        let v_display = if let Ok(v_str) = v.borrow().attr("__str__", vm.classes()) {
-            vm.push_tos(v.clone());
-            vm.push_tos(v_str);
-            vm.handle_callable_object("__str__", 1)?;
-            let v_display_ = vm.pop_tos();
-            if let Object::String(ref v_display) = *v_display_.borrow() {
-                v_display.clone()
-            } else {
-                return Err(RuntimeError::new("__str__ returned non-string"));
-            }
-        } else {
-            format!("<{v_class} object at {:p}>", &*v.borrow())
-        };
-        if matches!(*v.borrow(), Object::String(_)) {
-            display.push_str(&format!("'{v_display}'"));
-        } else {
-            display.push_str(&v_display);
-        }
+    // `{}` is an empty dict, so an empty set has to be shown by its constructor instead
+    if slf.is_empty() {
+        vm.push_tos(objref!(Object::String("set()".to_string())));
+        return Ok(());
+    }
 
-        // Only add a comma separation if there are more key-value pairs to output
-        if i < slf.len() - 1 {
-            display.push_str(", ");
-        }
+    if !vm.enter_repr(&slf_) {
+        vm.push_tos(objref!(Object::String("set(...)".to_string())));
+        return Ok(());
     }
-    vm.push_tos(objref!(Object::String(format!("{{{display}}}"))));
+    let items = slf
+        .iter()
+        .map(|v| std_lib::repr(vm, v))
+        .collect::<Result<Vec<_>, _>>();
+    vm.exit_repr();
+    vm.push_tos(objref!(Object::String(format!(
+        "{{{}}}",
+        items?.join(", ")
+    ))));
 
     Ok(())
 }
@@ -83,37 +73,24 @@ fn __iter__(vm: &mut VM) -> Result<(), RuntimeError> {
     let list_iter = elem_list.borrow().attr("__iter__", vm.classes())?;
     vm.push_tos(elem_list);
     vm.push_tos(list_iter);
-    vm.handle_callable_object("__iter__", 1)?;
+    vm.handle_callable_object(1)?;
 
     Ok(())
 }
 
 fn __contains__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
-    let Object::Set(ref slf) = *slf_.borrow_mut() else {
+    // Cloned so that comparing against the set itself doesn't need to borrow it again
+    let Object::Set(ref slf) = *slf_.borrow() else {
         panic!();
     };
+    let slf = slf.clone();
 
     let val = vm.pop_tos();
-    let Ok(val_eq) = val.borrow().attr("__eq__", vm.classes()) else {
-        vm.push_tos(objref!(Object::Boolean(false)));
-        return Ok(());
-    };
-
-    for item in slf {
-        vm.push_tos(item.clone());
-        vm.push_tos(val.clone());
-        vm.push_tos(val_eq.clone());
-        if vm.handle_callable_object("__eq__", 2).is_ok() {
-            let eq_res_ = vm.pop_tos();
-            let Object::Boolean(eq_res) = *eq_res_.borrow() else {
-                continue;
-            };
-
-            if eq_res {
-                vm.push_tos(eq_res_);
-                return Ok(());
-            }
+    for item in &slf {
+        if std_lib::eq(vm, &val, item)? {
+            vm.push_tos(objref!(Object::Boolean(true)));
+            return Ok(());
         }
     }
     vm.push_tos(objref!(Object::Boolean(false)));
@@ -1,6 +1,7 @@
 use super::super::objects::{Class, Object};
 use super::super::vm::RuntimeError;
-use crate::bytecode::VM;
+use crate::bytecode::std_lib::FormatSpec;
+use crate::bytecode::{VM, std_lib};
 use crate::{class_method, objref};
 
 pub fn init_class() -> Class {
@@ -8,7 +9,12 @@ pub fn init_class() -> Class {
 
     class_method!(class, __bool__, 1);
     class_method!(class, __str__, 1);
+    class_method!(class, __format__, 2);
     class_method!(class, __eq__, 2);
+    class_method!(class, __getitem__, 2);
+    class_method!(class, __add__, 2);
+    class_method!(class, __mul__, 2);
+    class_method!(class, __rmul__, 2);
     class_method!(class, __lt__, 2);
     class_method!(class, __le__, 2);
     class_method!(class, __gt__, 2);
@@ -17,6 +23,32 @@ pub fn init_class() -> Class {
     class
 }
 
+/// A string the way Python's `repr()` shows it: in single quotes, unless only double quotes avoid escaping.
+pub fn repr(s: &str) -> String {
+    let quote = if s.contains('\'') && !s.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+
+    let mut repr = String::from(quote);
+    for c in s.chars() {
+        match c {
+            '\\' => repr.push_str("\\\\"),
+            '\n' => repr.push_str("\\n"),
+            '\t' => repr.push_str("\\t"),
+            '\r' => repr.push_str("\\r"),
+            c if c == quote => {
+                repr.push('\\');
+                repr.push(c);
+            }
+            c => repr.push(c),
+        }
+    }
+    repr.push(quote);
+    repr
+}
+
 fn __bool__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
     let Object::String(ref slf) = *slf_.borrow() else {
@@ -31,6 +63,40 @@ fn __str__(_vm: &mut VM) -> Result<(), RuntimeError> {
     Ok(())
 }
 
+fn __format__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let Object::String(ref slf) = *slf_.borrow() else {
+        panic!();
+    };
+    let spec_ = vm.pop_tos();
+    let Object::String(ref spec) = *spec_.borrow() else {
+        return Err(RuntimeError::new("format spec must be a string"));
+    };
+    let spec = FormatSpec::parse(spec)?;
+
+    if let Some(code) = spec.ty.filter(|&c| c != 's') {
+        return Err(RuntimeError::new(&format!(
+            "unknown format code '{code}' for object of type 'String'"
+        )));
+    } else if spec.sign.is_some() {
+        return Err(RuntimeError::new(
+            "sign not allowed in string format specifier",
+        ));
+    } else if spec.align == Some('=') {
+        return Err(RuntimeError::new(
+            "'=' alignment not allowed in string format specifier",
+        ));
+    }
+    // The precision is how many characters to keep
+    let body = match spec.precision {
+        Some(precision) => slf.chars().take(precision).collect(),
+        None => slf.clone(),
+    };
+    vm.push_tos(objref!(Object::String(spec.pad("", &body, false))));
+
+    Ok(())
+}
+
 fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
     let Object::String(ref slf) = *slf_.borrow() else {
@@ -39,10 +105,8 @@ fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::String(ref other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'String' == '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf == other)));
@@ -50,6 +114,61 @@ fn __eq__(vm: &mut VM) -> Result<(), RuntimeError> {
     Ok(())
 }
 
+fn __getitem__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let Object::String(ref slf) = *slf_.borrow() else {
+        panic!();
+    };
+
+    let idx = std_lib::normalize_index(&vm.pop_tos().borrow(), slf.chars().count(), "string")?;
+    let c = slf
+        .chars()
+        .nth(idx)
+        .ok_or(RuntimeError::new("string index out of range"))?;
+    vm.push_tos(objref!(Object::String(c.to_string())));
+
+    Ok(())
+}
+
+fn __add__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let Object::String(ref slf) = *slf_.borrow() else {
+        panic!();
+    };
+
+    let other_ = vm.pop_tos();
+    let Object::String(ref other) = *other_.borrow() else {
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
+    };
+
+    vm.push_tos(objref!(Object::String(format!("{slf}{other}"))));
+
+    Ok(())
+}
+
+fn __mul__(vm: &mut VM) -> Result<(), RuntimeError> {
+    let slf_ = vm.pop_tos();
+    let Object::String(ref slf) = *slf_.borrow() else {
+        panic!();
+    };
+
+    let other_ = vm.pop_tos();
+    let Some(count) = std_lib::repeat_count(&other_.borrow())? else {
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
+    };
+
+    std_lib::repeated_len(slf.len(), count)?;
+    vm.push_tos(objref!(Object::String(slf.repeat(count))));
+
+    Ok(())
+}
+
+fn __rmul__(vm: &mut VM) -> Result<(), RuntimeError> {
+    __mul__(vm)
+}
+
 fn __lt__(vm: &mut VM) -> Result<(), RuntimeError> {
     let slf_ = vm.pop_tos();
     let Object::String(ref slf) = *slf_.borrow() else {
@@ -58,10 +177,8 @@ fn __lt__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::String(ref other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'String' < '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf < other)));
@@ -77,10 +194,8 @@ fn __le__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::String(ref other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'String' <= '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf <= other)));
@@ -96,10 +211,8 @@ fn __gt__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::String(ref other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'String' > '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf > other)));
@@ -115,10 +228,8 @@ fn __ge__(vm: &mut VM) -> Result<(), RuntimeError> {
 
     let other_ = vm.pop_tos();
     let Object::String(ref other) = *other_.borrow() else {
-        let other_class = other_.borrow().class(vm.classes()).name();
-        return Err(RuntimeError::new(&format!(
-            "`'String' >= '{other_class}'` is not a supported operation"
-        )));
+        vm.push_tos(objref!(Object::NotImplemented));
+        return Ok(());
     };
 
     vm.push_tos(objref!(Object::Boolean(slf >= other)));
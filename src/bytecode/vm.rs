@@ -1,18 +1,29 @@
 use core::panic;
 use std::error::Error;
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
+use std::io::{self, BufWriter, Write};
+use std::rc::Rc;
 
 use colored::Colorize;
 
 use super::OpCode;
 use super::objects::ObjectRef;
 use crate::bytecode::objects::{
-    Class, CodeObject, CompiledFunction, FrozenGenerator, FunctionType, Object,
+    Classes, CodeObject, CompiledFunction, FrozenGenerator, FunctionType, Object,
 };
 use crate::bytecode::{BytecodeEmitter, std_lib};
 use crate::objref;
 use crate::util::Map;
 
+/// How deeply comparisons and `deepcopy()` may go into nested containers before giving up, which also stops them on
+/// containers that contain themselves. Every level recurses through Rust. On the main thread's 8MB stack in a debug
+/// build, comparisons are the first to overflow, at about 1390 levels, so this leaves some room below that.
+pub const MAX_CONTAINER_DEPTH: usize = 1000;
+/// How many Python calls can be nested before a script is assumed to recurse forever, as in CPython.
+const DEFAULT_RECURSION_LIMIT: usize = 1000;
+/// How high scripts can raise the recursion limit through `setrecursionlimit()`, unless the embedder says otherwise.
+const DEFAULT_MAX_RECURSION_LIMIT: usize = 100_000;
+
 #[inline(always)]
 fn insufficient_items(instr: &str) -> String {
     format!("{instr} used with insufficient items on the stack")
@@ -39,16 +50,49 @@ impl Display for RuntimeError {
 
 impl Error for RuntimeError {}
 
+/// When the output written by `print` gets flushed to the VM's output writer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PrintFlush {
+    /// Flush after every `print`, so output is visible as soon as it's printed.
+    #[default]
+    Eager,
+    /// Only flush once the program stops running, which is faster for large amounts of piped output.
+    AtExit,
+}
+
+/// The buffered writer that all program output goes through. Defaults to stdout.
+pub struct Output(BufWriter<Box<dyn Write>>);
+
+impl Default for Output {
+    fn default() -> Self {
+        Self(BufWriter::new(Box::new(io::stdout())))
+    }
+}
+
+impl Debug for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Output")
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct VM {
     constants_pool: Vec<ObjectRef>,
     globals: Map<ObjectRef>,
     builtins: Map<ObjectRef>,
-    classes: Vec<Class>,
+    classes: Classes,
     frame_stack: Vec<Frame>,
     eval_stack: Vec<ObjectRef>,
     temp_stack: Vec<ObjectRef>,
     called_python_func: bool,
+    output: Output,
+    print_flush: PrintFlush,
+    comparison_depth: usize,
+    recursion_limit: usize,
+    /// Hard cap on `recursion_limit`, which scripts can't raise it past
+    max_recursion_limit: usize,
+    /// Containers whose `__str__()` is currently running, to catch the ones that contain themselves
+    repr_stack: Vec<ObjectRef>,
 }
 
 impl VM {
@@ -60,9 +104,51 @@ impl VM {
         };
         vm.constants_pool = constants_pool;
         vm.frame_stack.push(Frame::new(instructions, 0));
+        vm.recursion_limit = DEFAULT_RECURSION_LIMIT;
+        vm.max_recursion_limit = DEFAULT_MAX_RECURSION_LIMIT;
         vm
     }
 
+    /// Write all program output to `output` instead of stdout.
+    #[cfg(test)]
+    pub fn with_output(mut self, output: impl Write + 'static) -> Self {
+        self.output = Output(BufWriter::new(Box::new(output)));
+        self
+    }
+
+    /// Never let the recursion limit go above `max`, lowering the current limit if needed.
+    #[allow(dead_code)]
+    pub fn with_max_recursion_limit(mut self, max: usize) -> Self {
+        self.max_recursion_limit = max;
+        self.recursion_limit = self.recursion_limit.min(max);
+        self
+    }
+
+    /// Flush the output written by `print` according to `print_flush`.
+    pub fn with_print_flush(mut self, print_flush: PrintFlush) -> Self {
+        self.print_flush = print_flush;
+        self
+    }
+
+    /// Write `text` to the VM's output, flushing it right away if `PrintFlush::Eager` is set.
+    pub fn write_output(&mut self, text: &str) -> Result<(), RuntimeError> {
+        self.output
+            .0
+            .write_all(text.as_bytes())
+            .map_err(|e| RuntimeError::new(&format!("couldn't write output: {e}")))?;
+        if self.print_flush == PrintFlush::Eager {
+            self.flush_output()?;
+        }
+        Ok(())
+    }
+
+    fn flush_output(&mut self) -> Result<(), RuntimeError> {
+        self.output
+            .0
+            .flush()
+            .map_err(|e| RuntimeError::new(&format!("couldn't flush output: {e}")))
+    }
+
     pub fn pop_tos(&mut self) -> ObjectRef {
         self.eval_stack.pop().unwrap()
     }
@@ -79,38 +165,109 @@ impl VM {
         self.eval_stack.swap(len - 1, len - 2);
     }
 
-    pub fn classes(&self) -> &[Class] {
+    /// Must be paired with `exit_comparison()` once the nested comparison is done.
+    pub fn enter_comparison(&mut self) -> Result<(), RuntimeError> {
+        if self.comparison_depth >= MAX_CONTAINER_DEPTH {
+            return Err(RuntimeError::new(
+                "maximum recursion depth exceeded in comparison",
+            ));
+        }
+        self.comparison_depth += 1;
+        Ok(())
+    }
+
+    pub fn exit_comparison(&mut self) {
+        self.comparison_depth -= 1;
+    }
+
+    pub fn recursion_limit(&self) -> usize {
+        self.recursion_limit
+    }
+
+    pub fn set_recursion_limit(&mut self, limit: usize) -> Result<(), RuntimeError> {
+        if limit < 1 {
+            return Err(RuntimeError::new(
+                "recursion limit must be greater or equal than 1",
+            ));
+        } else if limit > self.max_recursion_limit {
+            return Err(RuntimeError::new(&format!(
+                "recursion limit can't exceed {}",
+                self.max_recursion_limit
+            )));
+        }
+        self.recursion_limit = limit;
+        Ok(())
+    }
+
+    /// Returns false if `container` is already being formatted further up, meaning it contains itself. Otherwise it must
+    /// be paired with `exit_repr()` once `container` is formatted.
+    pub fn enter_repr(&mut self, container: &ObjectRef) -> bool {
+        if self.repr_stack.iter().any(|c| Rc::ptr_eq(c, container)) {
+            return false;
+        }
+        self.repr_stack.push(container.clone());
+        true
+    }
+
+    pub fn exit_repr(&mut self) {
+        self.repr_stack.pop();
+    }
+
+    pub fn classes(&self) -> &Classes {
         &self.classes
     }
 
+    /// Run the program, reporting any runtime error on stderr.
     pub fn start(&mut self /*debug: Debug*/) {
+        if let Err(e) = self.run() {
+            eprintln!("{} {e}", "error:".red().bold());
+        }
+    }
+
+    /// Run the program, stopping at the first runtime error.
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
         // Register builtin functions
         self.builtins.insert("iter".to_string(), std_lib::iter_());
         self.builtins.insert("next".to_string(), std_lib::next_());
         self.builtins.insert("print".to_string(), std_lib::print_());
         self.builtins.insert("bool".to_string(), std_lib::bool_());
         self.builtins.insert("len".to_string(), std_lib::len_());
-
-        // Initialize and register builtin classes
-        // Order based on Object::class_idx()
-        self.classes.push(std_lib::none::init_class());
-        self.classes.push(std_lib::number::init_class());
-        self.classes.push(std_lib::boolean::init_class());
-        self.classes.push(std_lib::string::init_class());
-        self.classes.push(std_lib::list::init_class());
-        self.classes.push(std_lib::set::init_class());
-        self.classes.push(std_lib::dict::init_class());
-        self.classes.push(std_lib::code::init_class());
-        self.classes.push(std_lib::function::init_class());
-        self.classes.push(std_lib::generator::init_class());
+        self.builtins.insert("time".to_string(), std_lib::time_());
+        self.builtins.insert("abs".to_string(), std_lib::abs_());
+        self.builtins.insert("min".to_string(), std_lib::min_());
+        self.builtins.insert("max".to_string(), std_lib::max_());
+        self.builtins.insert("sum".to_string(), std_lib::sum_());
+        self.builtins.insert("sqrt".to_string(), std_lib::sqrt_());
+        self.builtins.insert("floor".to_string(), std_lib::floor_());
+        self.builtins.insert("ceil".to_string(), std_lib::ceil_());
+        self.builtins
+            .insert("deepcopy".to_string(), std_lib::deepcopy_());
+        self.builtins
+            .insert("format".to_string(), std_lib::format_());
+        self.builtins.insert(
+            "getrecursionlimit".to_string(),
+            std_lib::getrecursionlimit_(),
+        );
+        self.builtins.insert(
+            "setrecursionlimit".to_string(),
+            std_lib::setrecursionlimit_(),
+        );
+        self.builtins.insert(
+            "NotImplemented".to_string(),
+            objref!(Object::NotImplemented),
+        );
 
         // Finally run the code!
+        let mut result = Ok(());
         while let Some(frame) = self.frame_stack.last() {
             if let Err(e) = self.execute_opcode(frame.next_instruction()) {
-                eprintln!("{} {e}", "error:".red().bold());
-                return;
+                result = Err(e);
+                break;
             }
         }
+
+        // Flush even on an error, so that it gets reported after everything that was printed
+        result.and(self.flush_output())
     }
 
     fn execute_opcode(&mut self, instruction: OpCode) -> Result<(), RuntimeError> {
@@ -131,7 +288,7 @@ impl VM {
                 self.eval_stack.push(
                     self.eval_stack
                         .last()
-                        .expect(&insufficient_items("DUP_TOP"))
+                        .unwrap_or_else(|| panic!("{}", insufficient_items("DUP_TOP")))
                         .clone(),
                 );
             }
@@ -139,12 +296,12 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .last()
-                    .expect(&insufficient_items("INV_TOP"))
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("INV_TOP")))
                     .clone();
                 let inv_method = tos.borrow().class(&self.classes).attr("__inv__")?;
 
                 self.eval_stack.push(inv_method);
-                self.handle_callable_object("__inv__", 1)?;
+                self.handle_callable_object(1)?;
             }
             OpCode::JUMP_FORWARD(n) => {
                 inc_ip = false;
@@ -154,7 +311,7 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("JUMP_IF_FALSE"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("JUMP_IF_FALSE")));
                 if let Object::Boolean(b) = *tos.borrow() {
                     if !b {
                         inc_ip = false;
@@ -168,7 +325,7 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("JUMP_IF_TRUE"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("JUMP_IF_TRUE")));
                 if let Object::Boolean(b) = *tos.borrow() {
                     if b {
                         inc_ip = false;
@@ -190,7 +347,7 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .last()
-                    .expect(&insufficient_items("FOR_ITER"))
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("FOR_ITER")))
                     .clone();
                 let Object::Generator(ref mut generator) = *tos.borrow_mut() else {
                     return Err(RuntimeError::new(
@@ -199,6 +356,7 @@ impl VM {
                 };
 
                 if generator.is_done() {
+                    self.eval_stack.pop();
                     self.top_frame().inc_ip(n);
                 } else {
                     self.top_frame().inc_ip(1); // Must be done before pushing a new frame
@@ -207,11 +365,41 @@ impl VM {
                     self.eval_stack.extend_from_slice(generator.eval_stack());
                 }
             }
+            OpCode::FOR_STACK(n) => {
+                let tos = self
+                    .eval_stack
+                    .pop()
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("FOR_STACK")));
+                let Object::Number(left) = *tos.borrow() else {
+                    return Err(RuntimeError::new(
+                        "for loop over a list literal lost track of its items",
+                    ));
+                };
+
+                if left == 0.0 {
+                    inc_ip = false;
+                    self.top_frame().inc_ip(n);
+                } else {
+                    let item = self
+                        .eval_stack
+                        .pop()
+                        .unwrap_or_else(|| panic!("{}", insufficient_items("FOR_STACK")));
+                    // The count starts out as a shared constant, but every count after it is only ever on the stack
+                    let count = if Rc::strong_count(&tos) == 1 {
+                        *tos.borrow_mut() = Object::Number(left - 1.0);
+                        tos
+                    } else {
+                        objref!(Object::Number(left - 1.0))
+                    };
+                    self.eval_stack.push(count);
+                    self.eval_stack.push(item);
+                }
+            }
             OpCode::STORE_LOCAL(n) => {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("STORE_LOCAL"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("STORE_LOCAL")));
                 self.top_frame().set_local(n, tos);
             }
             // TODO: GH-10
@@ -220,7 +408,7 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("STORE_GLOBAL"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("STORE_GLOBAL")));
 
                 let name = self.constants_pool[n].clone();
                 let Object::String(ref name) = *name.borrow() else {
@@ -235,15 +423,15 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("STORE_ACCESS"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("STORE_ACCESS")));
                 let tos1 = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("STORE_ACCESS"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("STORE_ACCESS")));
                 let tos2 = self
                     .eval_stack
                     .last()
-                    .expect(&insufficient_items("STORE_ACCESS"))
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("STORE_ACCESS")))
                     .clone();
                 let set_item = tos2.borrow().attr("__setitem__", &self.classes)?;
 
@@ -251,7 +439,7 @@ impl VM {
                 self.eval_stack.push(tos1);
                 self.eval_stack.push(tos2);
                 self.eval_stack.push(set_item);
-                self.handle_callable_object("__setitem__", 3)?;
+                self.handle_callable_object(3)?;
             }
             OpCode::LOAD_CONST(n) => {
                 self.eval_stack.push(self.constants_pool[n].clone());
@@ -291,7 +479,7 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .last()
-                    .expect(&insufficient_items("STORE_ACCESS"))
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("STORE_ACCESS")))
                     .clone();
                 let name = self.constants_pool[n].clone();
                 let Object::String(ref name) = *name.borrow() else {
@@ -305,26 +493,47 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("LOAD_ACCESS"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("LOAD_ACCESS")));
                 let tos1 = self
                     .eval_stack
                     .last()
-                    .expect(&insufficient_items("LOAD_ACCESS"))
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("LOAD_ACCESS")))
                     .clone();
                 let get_item = tos1.borrow().attr("__getitem__", &self.classes)?;
 
                 self.eval_stack.push(tos);
                 self.eval_stack.push(tos1);
                 self.eval_stack.push(get_item);
-                self.handle_callable_object("__getitem__", 2)?;
+                self.handle_callable_object(2)?;
             }
-            OpCode::MAKE_FUNCTION(n, m) => {
+            OpCode::BINARY_OP(n) => {
+                let left = self
+                    .eval_stack
+                    .pop()
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("BINARY_OP")));
+                let right = self
+                    .eval_stack
+                    .pop()
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("BINARY_OP")));
+                let method = self.constants_pool[n].clone();
+                let Object::String(ref method) = *method.borrow() else {
+                    panic!("Constant object {n} expected to be a string, but is not");
+                };
+
+                let result = std_lib::binary_op(self, &left, method, &right)?;
+                self.eval_stack.push(result);
+            }
+            OpCode::MAKE_FUNCTION(n, m, name) => {
                 if !matches!(*self.constants_pool[m].borrow(), Object::Code(_)) {
                     panic!("Constant object {m} expected to be a code object, but is not");
                 };
+                let Object::String(ref name) = *self.constants_pool[name].borrow() else {
+                    panic!("Constant object {name} expected to be a string, but is not");
+                };
 
                 self.eval_stack
                     .push(objref!(Object::Function(CompiledFunction::new(
+                        name,
                         n,
                         FunctionType::Python(m)
                     ))));
@@ -336,7 +545,12 @@ impl VM {
                 inc_ip = false;
                 self.top_frame().inc_ip(1);
 
-                self.handle_callable_object("__call__", n)?;
+                let result_idx = self.eval_stack.len().saturating_sub(n + 1);
+                self.handle_callable_object(n)?;
+                // Rust functions that are only run for their side effects, like print(), don't push a result
+                if self.eval_stack.len() == result_idx {
+                    self.eval_stack.push(objref!(Object::None));
+                }
             }
             OpCode::BUILD_LIST(n) => {
                 let mut new_list = Vec::new();
@@ -344,7 +558,7 @@ impl VM {
                     let tos = self
                         .eval_stack
                         .pop()
-                        .expect(&insufficient_items("BUILD_LIST"));
+                        .unwrap_or_else(|| panic!("{}", insufficient_items("BUILD_LIST")));
                     new_list.push(tos);
                 }
                 self.eval_stack.push(objref!(Object::List(new_list)));
@@ -360,14 +574,10 @@ impl VM {
                     let tos = self
                         .eval_stack
                         .pop()
-                        .expect(&insufficient_items("BUILD_DICT"));
-                    if let Some(k) = key {
-                        new_dict.push((k, tos));
-                        key = None;
-                    } else if let Object::String(ref k) = *tos.borrow() {
-                        key = Some(k.clone());
-                    } else {
-                        panic!("PDP does not support building dicts with non-string keys");
+                        .unwrap_or_else(|| panic!("{}", insufficient_items("BUILD_DICT")));
+                    match key.take() {
+                        Some(k) => new_dict.push((k, tos)),
+                        None => key = Some(tos),
                     }
                 }
                 self.eval_stack.push(objref!(Object::Dict(new_dict)));
@@ -378,7 +588,7 @@ impl VM {
                     let tos = self
                         .eval_stack
                         .pop()
-                        .expect(&insufficient_items("BUILD_SET"));
+                        .unwrap_or_else(|| panic!("{}", insufficient_items("BUILD_SET")));
                     new_set.push(tos);
                 }
                 self.eval_stack.push(objref!(Object::Set(new_set)));
@@ -396,7 +606,7 @@ impl VM {
                         let tos = self
                             .eval_stack
                             .last()
-                            .expect(&insufficient_items("RETURN_VALUE"))
+                            .unwrap_or_else(|| panic!("{}", insufficient_items("RETURN_VALUE")))
                             .clone();
                         let Object::Generator(ref mut generator) = *tos.borrow_mut() else {
                             panic!(
@@ -410,7 +620,7 @@ impl VM {
                         let retval = self
                             .eval_stack
                             .pop()
-                            .expect(&insufficient_items("RETURN_VALUE"));
+                            .unwrap_or_else(|| panic!("{}", insufficient_items("RETURN_VALUE")));
                         self.eval_stack.truncate(old_frame.bytecode_offset);
                         self.eval_stack.push(retval);
                     }
@@ -423,7 +633,7 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("YIELD_VALUE"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("YIELD_VALUE")));
 
                 let frame = self
                     .frame_stack
@@ -433,7 +643,7 @@ impl VM {
                     let generator = self
                         .eval_stack
                         .last()
-                        .expect(&insufficient_items("YIELD_VALUE"))
+                        .unwrap_or_else(|| panic!("{}", insufficient_items("YIELD_VALUE")))
                         .clone();
                     let Object::Generator(ref mut generator) = *generator.borrow_mut() else {
                         panic!("TOS1 expected to be a generator, but is not {generator:?}");
@@ -463,14 +673,14 @@ impl VM {
                 let tos = self
                     .eval_stack
                     .pop()
-                    .expect(&insufficient_items("PUSH_TEMP"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("PUSH_TEMP")));
                 self.temp_stack.push(tos);
             }
             OpCode::POP_TEMP => {
                 let tempval = self
                     .temp_stack
                     .pop()
-                    .expect(&insufficient_items("POP_TEMP"));
+                    .unwrap_or_else(|| panic!("{}", insufficient_items("POP_TEMP")));
                 self.eval_stack.push(tempval);
             }
         }
@@ -482,16 +692,19 @@ impl VM {
         Ok(())
     }
 
-    pub fn handle_callable_object(
-        &mut self,
-        func_name: &str,
-        argc: usize,
-    ) -> Result<(), RuntimeError> {
+    pub fn handle_callable_object(&mut self, argc: usize) -> Result<(), RuntimeError> {
         let tos = self
             .eval_stack
             .last()
-            .expect(&insufficient_items("handle_callable_object()"))
+            .unwrap_or_else(|| panic!("{}", insufficient_items("handle_callable_object()")))
             .clone();
+
+        // Functions are run directly, because going through their `__call__()` would lose how many arguments were given
+        if let Object::Function(ref func) = *tos.borrow() {
+            self.eval_stack.pop();
+            return self.execute_function(func, argc);
+        }
+
         let tos_class = tos.borrow().class(&self.classes).name();
         let call = tos
             .borrow()
@@ -499,7 +712,7 @@ impl VM {
             .map_err(|_| RuntimeError::new(&format!("'{tos_class}' object is not callable")))?;
 
         if let Object::Function(ref func) = *call.borrow() {
-            self.execute_function(func_name, func, argc)?;
+            self.execute_function(func, argc)?;
         } else {
             return Err(RuntimeError::new(&format!(
                 "'{tos_class}' object is not callable"
@@ -511,7 +724,6 @@ impl VM {
 
     pub fn execute_function(
         &mut self,
-        func_name: &str,
         func: &CompiledFunction,
         argc: usize,
     ) -> Result<(), RuntimeError> {
@@ -519,8 +731,11 @@ impl VM {
             panic!("Not enough values in stack for argc {argc}");
         } else if !func.ignore_argc() && func.argc() != argc {
             return Err(RuntimeError::new(&format!(
-                "{func_name}() takes {} positional arguments but {argc} was given",
-                func.argc()
+                "{}() takes {} positional argument{} but {argc} {} given",
+                func.name(),
+                func.argc(),
+                if func.argc() == 1 { "" } else { "s" },
+                if argc == 1 { "was" } else { "were" },
             )));
         }
 
@@ -529,6 +744,10 @@ impl VM {
                 f(self)?;
             }
             FunctionType::Python(f_idx) => {
+                // The bottom frame is the module itself, so every other frame is a call in progress
+                if self.frame_stack.len() > self.recursion_limit {
+                    return Err(RuntimeError::new("maximum recursion depth exceeded"));
+                }
                 self.called_python_func = true;
                 let f_obj = self.constants_pool[*f_idx].clone();
                 let args = self.eval_stack.split_off(self.eval_stack.len() - argc);
@@ -560,7 +779,7 @@ impl VM {
         let tos = self
             .eval_stack
             .last()
-            .expect(&insufficient_items("handle_generator()"))
+            .unwrap_or_else(|| panic!("{}", insufficient_items("handle_generator()")))
             .clone();
         let Object::Generator(ref generator) = *tos.borrow() else {
             panic!("TOS must be a boolean when calling handle_generator()");
@@ -572,6 +791,34 @@ impl VM {
         Ok(())
     }
 
+    /// Runs `generator` up to its next value and returns it, or `None` if it was already done. Unlike `__next__()`, this
+    /// finishes before returning, for when Rust code needs the value right away.
+    pub fn resume_generator(
+        &mut self,
+        generator: &ObjectRef,
+    ) -> Result<Option<ObjectRef>, RuntimeError> {
+        if let Object::Generator(ref frozen) = *generator.borrow() {
+            if frozen.is_done() {
+                return Ok(None);
+            }
+        } else {
+            panic!("Called resume_generator() on a non-generator object");
+        }
+
+        let depth = self.frame_stack.len();
+        self.eval_stack.push(generator.clone());
+        self.handle_generator()?;
+        while self.frame_stack.len() > depth {
+            let instruction = self.top_frame().next_instruction();
+            self.execute_opcode(instruction)?;
+        }
+
+        // Yielding or returning leaves the value on top of the generator
+        let value = self.pop_tos();
+        self.pop_tos();
+        Ok(Some(value))
+    }
+
     #[inline(always)]
     fn top_frame(&mut self) -> &mut Frame {
         self.frame_stack
@@ -667,3 +914,894 @@ impl Frame {
         self.local_vars[local_idx] = new_value;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    use super::{
+        BytecodeEmitter, Classes, Object, ObjectRef, PrintFlush, RuntimeError, VM, std_lib,
+    };
+    use crate::parser::Parser;
+    use crate::util::with_main_thread_stack;
+
+    /// Records every chunk of output that reaches it, so tests can tell when it was flushed.
+    #[derive(Clone, Default)]
+    struct Chunks(Rc<RefCell<Vec<String>>>);
+
+    impl Write for Chunks {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0
+                .borrow_mut()
+                .push(String::from_utf8_lossy(buf).into_owned());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run_with(
+        source: &str,
+        print_flush: PrintFlush,
+    ) -> (VM, Result<(), RuntimeError>, Vec<String>) {
+        let (parse_results, symbol_table) = Parser::new().parse_from_str(source).unwrap();
+        let mut emitter = BytecodeEmitter::new(symbol_table);
+        emitter.emit(&parse_results.ast_node);
+
+        let chunks = Chunks::default();
+        let mut vm = VM::new(emitter)
+            .with_output(chunks.clone())
+            .with_print_flush(print_flush);
+        let result = vm.run();
+        (vm, result, chunks.0.take())
+    }
+
+    fn run(source: &str) -> VM {
+        let (vm, result, _) = run_with(source, PrintFlush::Eager);
+        result.unwrap();
+        vm
+    }
+
+    fn run_err(source: &str) -> String {
+        run_with(source, PrintFlush::Eager).1.unwrap_err().msg
+    }
+
+    fn output(source: &str) -> String {
+        let (_, result, chunks) = run_with(source, PrintFlush::Eager);
+        result.unwrap();
+        chunks.concat()
+    }
+
+    fn global(vm: &VM, name: &str) -> ObjectRef {
+        vm.globals
+            .get(name)
+            .unwrap_or_else(|| panic!("global `{name}` should exist"))
+            .clone()
+    }
+
+    #[test]
+    fn test_walrus_in_condition() {
+        let vm = run(concat!(
+            "items = [1, 2, 3, 4]\n",
+            "big = False\n",
+            "if (n := len(items)) > 3:\n",
+            "    big = True\n",
+            "m = n\n",
+            "while (k := len(items)) < 2:\n",
+            "    big = False\n",
+        ));
+
+        assert!(matches!(*global(&vm, "n").borrow(), Object::Number(4.0)));
+        assert!(matches!(*global(&vm, "m").borrow(), Object::Number(4.0)));
+        assert!(matches!(*global(&vm, "k").borrow(), Object::Number(4.0)));
+        assert!(matches!(
+            *global(&vm, "big").borrow(),
+            Object::Boolean(true)
+        ));
+    }
+
+    #[test]
+    fn test_print_flush() {
+        let source = "print(1)\nprint(\"two\")\nprint([3])\n";
+
+        let (_, _, chunks) = run_with(source, PrintFlush::Eager);
+        assert_eq!(chunks, vec!["1\n", "two\n", "[3]\n"]);
+
+        let (_, _, chunks) = run_with(source, PrintFlush::AtExit);
+        assert_eq!(chunks, vec!["1\ntwo\n[3]\n"]);
+
+        assert_eq!(output(source), "1\ntwo\n[3]\n");
+    }
+
+    #[test]
+    fn test_list_eq() {
+        assert_eq!(
+            output("print([1, [2, \"3\"]] == [1, [2, \"3\"]])\nprint([1, 2] == [2, 1])\n"),
+            "True\nFalse\n"
+        );
+        assert_eq!(
+            output("print([1] == [1, 1])\nprint([] == 1)\n"),
+            "False\nFalse\n"
+        );
+
+        // Self-referential lists are equal to themselves without comparing their items
+        assert_eq!(
+            output("a = [0]\na[0] = a\nprint(a == a)\nprint(a in a)\n"),
+            "True\nTrue\n"
+        );
+
+        with_main_thread_stack(|| {
+            // But comparing two distinct ones never finishes, so it's stopped
+            assert_eq!(
+                run_err("a = [0]\na[0] = a\nb = [0]\nb[0] = b\nc = a == b\n"),
+                "maximum recursion depth exceeded in comparison"
+            );
+
+            // Membership tests compare the same way, so they stop with the same error instead of answering False
+            let cycles = "a = [0]\na[0] = a\nb = [0]\nb[0] = b\n";
+            for membership in ["c = a in [b]\n", "c = a in {b}\n", "c = a in iter([b])\n"] {
+                assert_eq!(
+                    run_err(&format!("{cycles}{membership}")),
+                    "maximum recursion depth exceeded in comparison",
+                    "{membership}"
+                );
+            }
+
+            // Lists that are merely deep still compare
+            assert_eq!(
+                output(concat!(
+                    "a = [0]\nb = [0]\ni = 0\n",
+                    "while i < 998:\n    a = [a]\n    b = [b]\n    i += 1\n",
+                    "print(a == b)\n",
+                )),
+                "True\n"
+            );
+        });
+    }
+
+    #[test]
+    fn test_augmented_assignment() {
+        let vm = run("x = 1\nx += 2\ns = \"a\"\ns += \"x\"\n");
+        assert!(matches!(*global(&vm, "x").borrow(), Object::Number(3.0)));
+        assert!(matches!(*global(&vm, "s").borrow(), Object::String(ref s) if s == "ax"));
+        assert_eq!(output("l = [1, 5]\nl[1] -= 2\nprint(l)\n"), "[1, 3]\n");
+
+        // The target stays the left operand even though the value is evaluated first
+        assert_eq!(
+            output("x = 5\nx -= 2\nprint(x)\ny = 3\ny /= 4\nprint(y)\n"),
+            "3\n0.75\n"
+        );
+
+        // Local variables go through the same path
+        assert_eq!(
+            output("def f(n):\n    n *= 2\n    return n\nprint(f(4))\n"),
+            "8\n"
+        );
+    }
+
+    #[test]
+    fn test_number_display() {
+        assert_eq!(
+            output("print(2.5)\nprint(2)\nprint(4 / 2)\nprint(-0.5)\n"),
+            "2.5\n2\n2\n-0.5\n"
+        );
+    }
+
+    #[test]
+    fn test_time() {
+        let vm = run("a = time()\nb = time()\nordered = b >= a\n");
+        assert!(matches!(*global(&vm, "a").borrow(), Object::Number(a) if a > 0.0));
+        assert!(matches!(
+            *global(&vm, "ordered").borrow(),
+            Object::Boolean(true)
+        ));
+    }
+
+    #[test]
+    fn test_numeric_builtins() {
+        assert_eq!(
+            output(
+                "print(abs(-3))\nprint(abs(2.5))\nprint(min([3, 1, 2]))\nprint(max({1, 5, 2}))\n"
+            ),
+            "3\n2.5\n1\n5\n"
+        );
+        assert_eq!(
+            output("print(sum([1, 2, 3]))\nprint(sum([]))\nprint(max([\"b\", \"c\", \"a\"]))\n"),
+            "6\n0\nc\n"
+        );
+
+        // Anything with `__add__` can be summed
+        assert_eq!(output("print(sum([\"a\", \"b\", \"c\"]))\n"), "abc\n");
+
+        // Like `for`, they work through any iterable, not just lists and sets
+        assert_eq!(
+            output(concat!(
+                "print(min({\"b\": 1, \"a\": 2}))\n",
+                "print(sum(iter([1, 2])))\n",
+                "print(max(iter([4, 6, 5])))\n",
+            )),
+            "a\n3\n6\n"
+        );
+
+        assert_eq!(
+            run_err("x = abs(\"x\")\n"),
+            "bad operand type for abs(): 'String'"
+        );
+        assert_eq!(run_err("x = min([])\n"), "min() arg is an empty sequence");
+        assert_eq!(run_err("x = sum(3)\n"), "'Number' object is not iterable");
+    }
+
+    #[test]
+    fn test_postfix_chains() {
+        assert_eq!(
+            output(concat!(
+                "m = [[1, 2], [3, 4]]\n",
+                "m[1][0] = 9\n",
+                "print(m[0][1])\n",
+                "print(m[1])\n",
+                "def f():\n",
+                "    return [7, 8]\n",
+                "print(f()[1])\n",
+                "def g():\n",
+                "    print(\"called\")\n",
+                "    return f\n",
+                "callbacks = [g, f]\n",
+                "callbacks[0]()\n",
+                "print(callbacks[0]()()[0])\n",
+            )),
+            "2\n[9, 4]\n8\ncalled\ncalled\n7\n"
+        );
+
+        // Assigning through a call stores into whatever it returned
+        assert_eq!(
+            output(concat!(
+                "m = [[1, 2], [3, 4]]\n",
+                "def rows():\n",
+                "    return m\n",
+                "rows()[0] = [5, 6]\n",
+                "rows()[1][0] += 10\n",
+                "getters = [rows]\n",
+                "getters[0]()[1][1] = x = 0\n",
+                "print(m)\n",
+                "print(x)\n",
+            )),
+            "[[5, 6], [13, 0]]\n0\n"
+        );
+    }
+
+    #[test]
+    fn test_not_implemented_fallback() {
+        let mut vm = run("two = 2\nx = \"x\"\n");
+        let (two, x) = (global(&vm, "two"), global(&vm, "x"));
+
+        // `2 + "x"` tries `Number.__add__()`, which doesn't support the other operand, and String has no `__radd__()`
+        let forward = std_lib::binary_method(&mut vm, &two, "__add__", &x).unwrap();
+        assert!(matches!(*forward.borrow(), Object::NotImplemented));
+        assert_eq!(
+            run_err("y = 2 + \"x\"\n"),
+            "unsupported operand type(s) for +: 'Number' and 'String'"
+        );
+        assert_eq!(
+            run_err("y = \"x\" < 2\n"),
+            "'<' not supported between instances of 'String' and 'Number'"
+        );
+
+        // `2 * [1]` tries `Number.__mul__()` first, which gives up, and then the list's reflected `__rmul__()`
+        let mut vm = run("two = 2\nl = [1]\nx = \"x\"\n");
+        let (two, l, x) = (global(&vm, "two"), global(&vm, "l"), global(&vm, "x"));
+        let forward = std_lib::binary_method(&mut vm, &two, "__mul__", &l).unwrap();
+        assert!(matches!(*forward.borrow(), Object::NotImplemented));
+        assert_eq!(
+            output("print(2 * [1])\nprint(3 * \"ab\")\n"),
+            "[1, 1]\nababab\n"
+        );
+
+        // In `[1] * "x"` both sides give up, so only then is it an error
+        let forward = std_lib::binary_method(&mut vm, &l, "__mul__", &x).unwrap();
+        assert!(matches!(*forward.borrow(), Object::NotImplemented));
+        let reflected = std_lib::binary_method(&mut vm, &x, "__rmul__", &l).unwrap();
+        assert!(matches!(*reflected.borrow(), Object::NotImplemented));
+        assert_eq!(
+            run_err("y = [1] * \"x\"\n"),
+            "unsupported operand type(s) for *: 'List' and 'String'"
+        );
+
+        assert_eq!(
+            output(concat!(
+                "print(2 == \"x\")\n",
+                "print(2 != \"x\")\n",
+                "print(\"a\" + \"b\")\n",
+                "print(NotImplemented)\n",
+            )),
+            "False\nTrue\nab\nNotImplemented\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_container_display() {
+        assert_eq!(
+            output(concat!(
+                "print([{\"a\": 1}, {\"b\": 2}])\n",
+                "print({\"xs\": [1, 2], \"ys\": [\"p\", \"q\"]})\n",
+                "print([{1, \"one\"}, [], {}])\n",
+                "print([\"it's\", \"say \\\"hi\\\"\"])\n",
+                "print({2})\n",
+            )),
+            concat!(
+                "[{'a': 1}, {'b': 2}]\n",
+                "{'xs': [1, 2], 'ys': ['p', 'q']}\n",
+                "[{1, 'one'}, [], {}]\n",
+                "[\"it's\", 'say \"hi\"']\n",
+                "{2}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_number_eq_edge_cases() {
+        assert_eq!(
+            output(concat!(
+                "z = -0.0\n",
+                "print(0.0 == z)\n",
+                "print(z < 0.0)\n",
+                "print(z <= 0.0)\n",
+                "print(z >= 0.0)\n",
+                "print(False == 0.0)\n",
+                "print(0 == False)\n",
+                "print(True == 1)\n",
+                "print(True == 2)\n",
+                "print(1 != True)\n",
+            )),
+            "True\nFalse\nTrue\nTrue\nTrue\nTrue\nTrue\nFalse\nFalse\n"
+        );
+    }
+
+    #[test]
+    fn test_recursive_container_display() {
+        assert_eq!(
+            output(concat!(
+                "xs = [1, 2]\n",
+                "xs[1] = xs\n",
+                "print(xs)\n",
+                "d = {\"a\": 1, \"b\": []}\n",
+                "d[\"b\"] = [d, xs]\n",
+                "print(d)\n",
+                "s = {1, [2]}\n",
+                "print([s, s])\n",
+            )),
+            concat!(
+                "[1, [...]]\n",
+                "{'a': 1, 'b': [{...}, [1, [...]]]}\n",
+                "[{1, [2]}, {1, [2]}]\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_continue_in_for() {
+        assert_eq!(
+            output(concat!(
+                "for x in [1, 2, 3, 4]:\n",
+                "    if x == 2:\n",
+                "        continue\n",
+                "    print(x)\n",
+                "n = 0\n",
+                "for y in [5, 6, 7]:\n",
+                "    n += 1\n",
+                "    continue\n",
+                "print(n)\n",
+            )),
+            "1\n3\n4\n3\n"
+        );
+    }
+
+    #[test]
+    fn test_sequence_repetition() {
+        assert_eq!(
+            output(concat!(
+                "print(\"ab\" * 3)\n",
+                "print(2 * [1, \"x\"])\n",
+                "n = -1\n",
+                "print(\"ab\" * 0 == \"\")\n",
+                "print(\"ab\" * n == \"\")\n",
+                "print([1] * 0)\n",
+                "print(n * [1])\n",
+            )),
+            "ababab\n[1, 'x', 1, 'x']\nTrue\nTrue\n[]\n[]\n"
+        );
+
+        assert_eq!(
+            run_err("x = \"ab\" * 1.5\n"),
+            "can't multiply sequence by a non-integer number"
+        );
+        assert_eq!(
+            run_err("x = [1] * \"ab\"\n"),
+            "unsupported operand type(s) for *: 'List' and 'String'"
+        );
+
+        // Counts too big to build are errors rather than overflows or failed allocations
+        for source in [
+            "x = [1, 2] * 10000000000000000000\n",
+            "x = [1] * 100000000000000000000000\n",
+            "x = \"ab\" * 10000000000000000000\n",
+            "x = 100000000 * \"a\"\n",
+        ] {
+            assert_eq!(run_err(source), "repeated sequence is too long", "{source}");
+        }
+    }
+
+    #[test]
+    fn test_math_builtins() {
+        assert_eq!(
+            output(concat!(
+                "print(sqrt(9) == 3)\n",
+                "print(floor(3.7) == 3)\n",
+                "print(ceil(3.2) == 4)\n",
+                "print(sqrt(2.25))\n",
+                "n = -3.5\n",
+                "print(floor(n))\n",
+                "print(ceil(n))\n",
+            )),
+            "True\nTrue\nTrue\n1.5\n-4\n-3\n"
+        );
+
+        assert_eq!(run_err("n = -1\nx = sqrt(n)\n"), "math domain error");
+        assert_eq!(
+            run_err("x = floor(\"3\")\n"),
+            "floor() argument must be a number, not 'String'"
+        );
+    }
+
+    #[test]
+    fn test_none_literal() {
+        assert_eq!(
+            output("x = None\nprint(x)\nprint([None, 1])\nprint(x == None)\n"),
+            "None\n[None, 1]\nTrue\n"
+        );
+    }
+
+    #[test]
+    fn test_chained_assignment() {
+        // One list, bound to both names
+        assert_eq!(output("a = b = [0]\nb[0] = 1\nprint(a)\n"), "[1]\n");
+
+        // The value is evaluated once, then assigned from left to right
+        assert_eq!(
+            output(concat!(
+                "def f():\n",
+                "    print(\"evaluated\")\n",
+                "    return 2\n",
+                "l = [0, 0]\n",
+                "x = l[1] = y = f()\n",
+                "print(l)\n",
+                "print(x + y)\n",
+                "def g():\n",
+                "    p = q = 3\n",
+                "    return p * q\n",
+                "print(g())\n",
+            )),
+            "evaluated\n[0, 2]\n4\n9\n"
+        );
+    }
+
+    #[test]
+    fn test_multi_line_brackets() {
+        assert_eq!(
+            output(concat!(
+                "x = [\n",
+                "    1,  # first\n",
+                "    # on a line of its own\n",
+                "  2\n",
+                "]\n",
+                "if len([1,\n",
+                "        2]) == 2:\n",
+                "    print(x)\n",
+            )),
+            "[1, 2]\n"
+        );
+    }
+
+    #[test]
+    fn test_lazy_classes() {
+        // Only the classes a script actually uses get built
+        let vm = run("x = 1 + 2\n");
+        let initialized = (0..std_lib::BUILTIN_CLASSES.len())
+            .filter(|idx| vm.classes().is_initialized(*idx))
+            .map(|idx| vm.classes().get(idx).name())
+            .collect::<Vec<_>>();
+        assert_eq!(initialized, vec!["Number"]);
+
+        // And each one comes out the same as building it directly
+        let classes = Classes::default();
+        for (idx, init_class) in std_lib::BUILTIN_CLASSES.iter().enumerate() {
+            let eager = init_class();
+            let lazy = classes.get(idx);
+            assert_eq!(lazy.name(), eager.name());
+            for method in ["__str__", "__eq__", "__bool__"] {
+                assert_eq!(lazy.attr(method).is_ok(), eager.attr(method).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_recursion_limit() {
+        let recurse = concat!(
+            "def depth(n):\n",
+            "    if n == 0:\n",
+            "        return 0\n",
+            "    return depth(n - 1) + 1\n",
+        );
+
+        assert_eq!(
+            output(&format!(
+                "{recurse}print(getrecursionlimit())\nprint(depth(500))\n"
+            )),
+            "1000\n500\n"
+        );
+        assert_eq!(
+            run_err(&format!(
+                "{recurse}setrecursionlimit(100)\nx = depth(500)\n"
+            )),
+            "maximum recursion depth exceeded"
+        );
+        assert_eq!(
+            run_err(&format!("{recurse}x = depth(5000)\n")),
+            "maximum recursion depth exceeded"
+        );
+        assert_eq!(
+            output(&format!(
+                "{recurse}setrecursionlimit(6000)\nprint(getrecursionlimit())\nprint(depth(5000))\n"
+            )),
+            "6000\n5000\n"
+        );
+
+        assert_eq!(
+            run_err("setrecursionlimit(0)\n"),
+            "recursion limit must be greater or equal than 1"
+        );
+        assert_eq!(
+            run_err("setrecursionlimit(2.5)\n"),
+            "recursion limit must be an integer"
+        );
+        assert_eq!(
+            run_err("setrecursionlimit(1000000)\n"),
+            "recursion limit can't exceed 100000"
+        );
+
+        // The embedder's cap wins over both the default limit and the script
+        let (parse_results, symbol_table) = Parser::new()
+            .parse_from_str("print(getrecursionlimit())\nsetrecursionlimit(60)\n")
+            .unwrap();
+        let mut emitter = BytecodeEmitter::new(symbol_table);
+        emitter.emit(&parse_results.ast_node);
+        let chunks = Chunks::default();
+        let mut vm = VM::new(emitter)
+            .with_output(chunks.clone())
+            .with_max_recursion_limit(50);
+        assert_eq!(vm.run().unwrap_err().msg, "recursion limit can't exceed 50");
+        assert_eq!(chunks.0.take().concat(), "50\n");
+    }
+
+    #[test]
+    fn test_deepcopy() {
+        assert_eq!(
+            output(concat!(
+                "a = [[1, 2], [3]]\n",
+                "b = deepcopy(a)\n",
+                "b[0][0] = 9\n",
+                "print(a)\n",
+                "print(b)\n",
+                "d = {\"k\": [1]}\n",
+                "e = deepcopy(d)\n",
+                "e[\"k\"][0] = 2\n",
+                "print(d)\n",
+            )),
+            "[[1, 2], [3]]\n[[9, 2], [3]]\n{'k': [1]}\n"
+        );
+
+        // Shared references stay shared, and cycles are copied as cycles
+        assert_eq!(
+            output(concat!(
+                "s = [1]\n",
+                "c = deepcopy([s, s])\n",
+                "c[0][0] = 5\n",
+                "print(c)\n",
+                "l = [1, 2]\n",
+                "l[0] = l\n",
+                "m = deepcopy(l)\n",
+                "m[1] = 3\n",
+                "print(m)\n",
+                "print(l)\n",
+            )),
+            "[[5], [5]]\n[[...], 3]\n[[...], 2]\n"
+        );
+
+        // Copying recurses once per nesting level, so it gives up cleanly instead of overflowing the stack
+        let nested =
+            |depth: usize| format!("l = []\ni = 0\nwhile i < {depth}:\n    l = [l]\n    i += 1\n");
+        with_main_thread_stack(|| {
+            assert_eq!(
+                output(&format!("{}print(len(deepcopy(l)))\n", nested(999))),
+                "1\n"
+            );
+            assert_eq!(
+                run_err(&format!("{}m = deepcopy(l)\n", nested(1000))),
+                "maximum recursion depth exceeded while copying"
+            );
+        });
+    }
+
+    #[test]
+    fn test_wrong_argument_count() {
+        let greet = "def greet(name):\n    print(name)\n";
+        assert_eq!(
+            run_err(&format!("{greet}greet(1, 2)\n")),
+            "greet() takes 1 positional argument but 2 were given"
+        );
+        assert_eq!(
+            run_err(&format!("{greet}greet()\n")),
+            "greet() takes 1 positional argument but 0 were given"
+        );
+        assert_eq!(
+            run_err("print(1, 2)\n"),
+            "print() takes 1 positional argument but 2 were given"
+        );
+        assert_eq!(
+            run_err("def f():\n    return 1\nx = f(3)\n"),
+            "f() takes 0 positional arguments but 1 was given"
+        );
+    }
+
+    #[test]
+    fn test_in_generator() {
+        // The generator only runs up to the match, so the rest of it is still there afterwards
+        assert_eq!(
+            output(concat!(
+                "g = iter([1, 2, 3, 4])\n",
+                "print(2 in g)\n",
+                "print(next(g))\n",
+                "print(1000000 in g)\n",
+                "print(1 in iter([]))\n",
+                "print(3 not in iter([1, 2, 3]))\n",
+            )),
+            "True\n3\nFalse\nFalse\nFalse\n"
+        );
+
+        // Either side can be more than a single load
+        assert_eq!(
+            output("x = [1, 2]\nprint(x[0] in [3, 1])\nprint(len(x) in iter([2]))\n"),
+            "True\nTrue\n"
+        );
+    }
+
+    #[test]
+    fn test_negative_indexing() {
+        for (sequence, kind) in [("\"abc\"", "string"), ("[\"a\", \"b\", \"c\"]", "list")] {
+            assert_eq!(
+                output(&format!(
+                    "s = {sequence}\nn = -1\nprint(s[n])\nn = -3\nprint(s[n])\nn = -0\nprint(s[n])\nprint(s[2])\n"
+                )),
+                "c\na\na\nc\n"
+            );
+            for idx in ["-4", "3"] {
+                assert_eq!(
+                    run_err(&format!("s = {sequence}\nn = {idx}\nx = s[n]\n")),
+                    format!("{kind} index out of range")
+                );
+            }
+            assert_eq!(
+                run_err(&format!("s = {sequence}\nx = s[1.5]\n")),
+                format!("{kind} indices must be integers")
+            );
+        }
+    }
+
+    #[test]
+    fn test_dict_object_keys() {
+        assert_eq!(
+            output(concat!(
+                "d = {\"a\": 1}\n",
+                "d[1] = \"one\"\n",
+                "d[2.5] = \"half\"\n",
+                "print(d[1])\n",
+                "d[1] = \"uno\"\n",
+                "print(d[1])\n",
+                "print(1 in d)\n",
+                "print(3 in d)\n",
+                "print(d)\n",
+            )),
+            "one\nuno\nTrue\nFalse\n{'a': 1, 1: 'uno', 2.5: 'half'}\n"
+        );
+
+        assert_eq!(
+            run_err("d = {\"a\": 1}\nx = d[2]\n"),
+            "key 2 not found in dict"
+        );
+        assert_eq!(
+            run_err("d = {\"a\": 1}\nd[[1]] = 2\n"),
+            "unhashable type: 'List'"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(
+            output(concat!(
+                "print(format(3.14159, \".2f\"))\n",
+                "print(format(7, \"03d\"))\n",
+                "n = -7\n",
+                "print(format(n, \"05d\"))\n",
+                "print(format(42, \"*^7\"))\n",
+                "print(format(0.5, \"+.1%\"))\n",
+                "print(format(-0.0, \".1f\"))\n",
+                "print(format(\"abc\", \">5\"))\n",
+                "print(format(\"abcdef\", \".2\"))\n",
+                "print(format([1], \"\"))\n",
+            )),
+            "3.14\n007\n-0007\n**42***\n+50.0%\n-0.0\n  abc\nab\n[1]\n"
+        );
+
+        assert_eq!(
+            run_err("format(1.5, \"d\")\n"),
+            "unknown format code 'd' for object of type 'Number'"
+        );
+        assert_eq!(
+            run_err("format(\"a\", \"+\")\n"),
+            "sign not allowed in string format specifier"
+        );
+        assert_eq!(
+            run_err("format(1, \"5.\")\n"),
+            "format specifier missing precision"
+        );
+        for spec in [
+            "99999999999999",
+            ".99999999999999999999999",
+            "99999999999999999999999",
+        ] {
+            assert_eq!(
+                run_err(&format!("format(1, \"{spec}\")\n")),
+                "too many decimal digits in format string",
+                "{spec}"
+            );
+        }
+        assert_eq!(
+            run_err("format([1], \">3\")\n"),
+            "unsupported format string passed to List.__format__"
+        );
+    }
+
+    #[test]
+    fn test_in_line_jumps() {
+        assert_eq!(
+            output(concat!(
+                "while True: break\n",
+                "for i in [1, 2, 3, 4]:\n",
+                "    if i == 2: continue\n",
+                "    if i == 4: break\n",
+                "    print(i)\n",
+                "def f(x):\n",
+                "    if x: return x\n",
+                "    return 0\n",
+                "print(f(5))\n",
+                "print(f(False))\n",
+            )),
+            "1\n3\n5\n0\n"
+        );
+    }
+
+    #[test]
+    fn test_none_comparisons() {
+        assert_eq!(
+            output(concat!(
+                "print(None == None)\n",
+                "print(None != None)\n",
+                "print(None == 0)\n",
+                "print(None != 0)\n",
+            )),
+            "True\nFalse\nFalse\nTrue\n"
+        );
+
+        // Like in Python, `None` can be checked for equality but not ordered
+        assert_eq!(
+            run_err("x = None < None\n"),
+            "'<' not supported between instances of 'NoneType' and 'NoneType'"
+        );
+        assert_eq!(
+            run_err("x = None >= 1\n"),
+            "'>=' not supported between instances of 'NoneType' and 'Number'"
+        );
+    }
+
+    #[test]
+    fn test_for_over_list_literal() {
+        // Every program runs the same whether it loops over a literal or a generator
+        let source = concat!(
+            "for x in ITEMS:\n",
+            "    if x == 2: continue\n",
+            "    if x == 4: break\n",
+            "    print(x)\n",
+            "for a in ITEMS:\n",
+            "    for b in [\"a\", \"b\"]:\n",
+            "        if b == \"b\": break\n",
+            "        print(b)\n",
+            "def f():\n",
+            "    for x in ITEMS:\n",
+            "        if x == 3: return x\n",
+            "print(f())\n",
+            "for x in ITEMS:\n",
+            "    def g():\n",
+            "        return x * 2\n",
+            "    if x > 2: print(g())\n",
+            // Calls and inner generator loops must not leave anything on the stack for the outer loop to find
+            "def show(a):\n",
+            "    print(a)\n",
+            "def h(a):\n",
+            "    return a\n",
+            "for x in ITEMS:\n",
+            "    show(x)\n",
+            "    h(x)\n",
+            "print(x)\n",
+            "others = [\"c\", \"d\"]\n",
+            "for a in ITEMS:\n",
+            "    for b in others:\n",
+            "        if b == \"d\": break\n",
+            "        show(b)\n",
+            "for a in ITEMS:\n",
+            "    for b in others:\n",
+            "        x = b\n",
+            "print(x)\n",
+        );
+        let literal_source = source.replace("ITEMS", "[1, 2, 3, 4, 5]");
+        let generated_source = format!(
+            "items = [1, 2, 3, 4, 5]\n{}",
+            source.replace("ITEMS", "items")
+        );
+        let literal = output(&literal_source);
+        let generated = output(&generated_source);
+        assert_eq!(
+            literal,
+            "1\n3\na\na\na\na\na\n3\n6\n8\n10\n1\n2\n3\n4\n5\n5\nc\nc\nc\nc\nc\nd\n"
+        );
+        assert_eq!(literal, generated);
+        // Only the module's own return value is left once it finishes
+        assert_eq!(run(&literal_source).eval_stack.len(), 1);
+        assert_eq!(run(&generated_source).eval_stack.len(), 1);
+
+        // The items are all evaluated before the first iteration, and an empty literal runs nothing
+        assert_eq!(
+            output(concat!(
+                "l = [0]\n",
+                "for x in [len(l), len(l)]:\n",
+                "    l = [0, 0]\n",
+                "    print(x)\n",
+                "for x in []:\n",
+                "    print(x)\n",
+            )),
+            "1\n1\n"
+        );
+    }
+
+    #[test]
+    fn test_sibling_functions_use_own_symbols() {
+        // Each definition compiles against its own symbol table, not the first sibling's
+        assert_eq!(
+            output(concat!(
+                "def f(a):\n",
+                "    x = a + 1\n",
+                "    return x\n",
+                "def g(b):\n",
+                "    y = 2\n",
+                "    z = b * y\n",
+                "    w = z + y\n",
+                "    return w\n",
+                "print(f(1))\n",
+                "print(g(3))\n",
+            )),
+            "2\n8\n"
+        );
+    }
+}
@@ -55,7 +55,13 @@ fn main() {
         )))
         .init();
 
-    let parser = parser::Parser::new();
+    let mut parser = parser::Parser::new();
+    if let Some(max) = env_option("PDP_MAX_NESTING_DEPTH", |v| v.parse().ok()) {
+        parser = parser.with_max_nesting_depth(max);
+    }
+    if env_option("PDP_KEEP_COMMENTS", flag) == Some(true) {
+        parser = parser.with_comments();
+    }
     let (parse_results, symbol_table) = match parser.parse_from_file("testing.py") {
         Ok(r) => r,
         Err(e) => {
@@ -74,5 +80,35 @@ fn main() {
 
     info!("Starting up the VM");
     let mut vm = bytecode::VM::new(emitter);
+    let print_flush = env_option("PDP_PRINT_FLUSH", |v| match v {
+        "eager" => Some(bytecode::PrintFlush::Eager),
+        "at-exit" => Some(bytecode::PrintFlush::AtExit),
+        _ => None,
+    });
+    if let Some(print_flush) = print_flush {
+        vm = vm.with_print_flush(print_flush);
+    }
     vm.start();
 }
+
+/// The value of the environment variable `name` as read by `parse`, or `None` if it isn't set. Exits if `parse` rejects
+/// the value, since running with a different configuration than the one asked for would be confusing.
+fn env_option<T>(name: &str, parse: impl FnOnce(&str) -> Option<T>) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    match parse(&value) {
+        Some(v) => Some(v),
+        None => {
+            eprintln!("invalid value for {name}: `{value}`");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reads `1` and `0` as an on/off switch.
+fn flag(value: &str) -> Option<bool> {
+    match value {
+        "1" => Some(true),
+        "0" => Some(false),
+        _ => None,
+    }
+}
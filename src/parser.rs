@@ -5,7 +5,7 @@ pub mod ptag;
 pub mod symbol_table;
 mod tpg;
 
-use std::{fmt::Display, fs, sync::OnceLock};
+use std::{cell::RefCell, fmt::Display, fs};
 
 use colored::Colorize;
 use log::{info, warn};
@@ -15,8 +15,10 @@ use crate::parser::{
     tpg::{ParseTokensRes, ProgramNode},
 };
 
-static FILENAME: OnceLock<String> = OnceLock::new();
-static LINES: OnceLock<Vec<String>> = OnceLock::new();
+thread_local! {
+    static FILENAME: RefCell<Option<String>> = const { RefCell::new(None) };
+    static LINES: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
 
 #[derive(Debug)]
 enum ParseErrorType {
@@ -24,6 +26,8 @@ enum ParseErrorType {
         filename: String,
         line: usize,
         col: usize,
+        /// How many characters get underlined, starting from `col`
+        width: usize,
         line_string: String,
     },
     General,
@@ -33,6 +37,8 @@ enum ParseErrorType {
 pub struct ParseError {
     err_type: ParseErrorType,
     pub msg: String,
+    /// Whether a quantifier may still discard this error when the failed node only consumed its first token
+    recoverable: bool,
 }
 
 impl Display for ParseError {
@@ -42,10 +48,11 @@ impl Display for ParseError {
                 filename,
                 line,
                 col,
+                width,
                 line_string,
             } => {
                 let location = format!("{filename}:{}:{}", line + 1, col + 1);
-                let cursor = str::repeat(" ", col + 1) + "^";
+                let cursor = str::repeat(" ", col + 1) + &str::repeat("^", *width);
                 f.write_str(&format!(
                     "({location}) {} {}\n  {} {line_string}\n   {}",
                     "error:".red().bold(),
@@ -66,47 +73,91 @@ impl ParseError {
         Self {
             err_type: ParseErrorType::General,
             msg: msg.to_string(),
+            recoverable: true,
         }
     }
 
     pub fn marked(msg: &str, line: usize, col: usize) -> Self {
-        let filename = FILENAME.get_or_init(|| "unset".to_string());
-        let line_string = match LINES.get() {
-            Some(s) => {
+        let filename = FILENAME.with_borrow(|f| f.clone().unwrap_or("unset".to_string()));
+        let line_string = match LINES.with_borrow(|lines| {
+            lines.as_ref().map(|s| {
                 if s.is_empty() {
-                    &"this should only exist for an error that gets thrown out".to_string()
+                    "this should only exist for an error that gets thrown out".to_string()
                 } else {
-                    &s[line]
+                    s[line].clone()
                 }
-            }
+            })
+        }) {
+            Some(s) => s,
             None => return Self::general("Fatal error: lines were never set"),
         };
 
         Self {
             err_type: ParseErrorType::Marked {
-                filename: filename.clone(),
+                filename,
                 line,
                 col,
-                line_string: line_string.clone(),
+                width: 1,
+                line_string,
             },
             msg: msg.to_string(),
+            recoverable: true,
+        }
+    }
+
+    /// Underline the `width` characters from the marked column on instead of only the first, so that an error about a
+    /// token like `+=` points at all of it.
+    pub fn spanning(mut self, width: usize) -> Self {
+        if let ParseErrorType::Marked { width: w, .. } = &mut self.err_type {
+            *w = width.max(1);
         }
+        self
     }
+
+    /// Make sure the error gets reported even if the failed node only consumed its first token, for when that token
+    /// alone shows what's wrong.
+    pub fn unrecoverable(mut self) -> Self {
+        self.recoverable = false;
+        self
+    }
+}
+
+pub struct Parser {
+    max_nesting_depth: usize,
+    keep_comments: bool,
 }
 
-#[derive(Default)]
-pub struct Parser {}
+impl Default for Parser {
+    fn default() -> Self {
+        Self {
+            max_nesting_depth: tpg::DEFAULT_MAX_NESTING_DEPTH,
+            keep_comments: false,
+        }
+    }
+}
 
 impl Parser {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Reject programs whose expressions and blocks nest more than `max` levels deep.
+    pub fn with_max_nesting_depth(mut self, max: usize) -> Self {
+        self.max_nesting_depth = max;
+        self
+    }
+
+    /// Keep `#` comments as `COMMENT` tokens in the token stream output. They are still dropped before parsing.
+    pub fn with_comments(mut self) -> Self {
+        self.keep_comments = true;
+        self
+    }
+
     pub fn parse_from_file(
         self,
         filename: &str,
     ) -> Result<(ParseTokensRes<ProgramNode>, SymbolTable), ParseError> {
-        FILENAME.set(filename.to_string()).unwrap();
+        FILENAME.set(Some(filename.to_string()));
         let script =
             fs::read_to_string(filename).map_err(|e| ParseError::general(&e.to_string()))?;
 
@@ -118,16 +169,19 @@ impl Parser {
         script: &str,
     ) -> Result<(ParseTokensRes<ProgramNode>, SymbolTable), ParseError> {
         info!("Producing token stream");
-        let mut lex = lexer::Lexer::new();
+        let mut lex = if self.keep_comments {
+            lexer::Lexer::new().with_comments()
+        } else {
+            lexer::Lexer::new()
+        };
 
-        LINES
-            .set(script.lines().map(|l| l.to_string()).collect())
-            .unwrap();
+        let lines = script.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+        LINES.set(Some(lines.clone()));
 
-        for (line, line_str) in LINES.get().unwrap().iter().enumerate() {
+        for (line, line_str) in lines.iter().enumerate() {
             let line_chars = line_str.chars().collect::<Vec<char>>();
-            // Not `line_str.len() - 1` because we want to count the excluded newline
-            let max_col = line_str.len();
+            // Not `line_chars.len() - 1` because we want to count the excluded newline
+            let max_col = line_chars.len();
             let mut col = 0;
 
             // Keep identifying lexemes until the line is finished being scanned.
@@ -146,7 +200,12 @@ impl Parser {
             }
         }
 
-        let token_stream = lex.finalize().map_err(|e| ParseError::general(&e))?;
+        // Errors at the very end of the input are marked right after its last character
+        let eof_line = lines.len().saturating_sub(1);
+        let eof_col = lines.last().map_or(0, |l| l.chars().count());
+        let token_stream = lex
+            .finalize()
+            .map_err(|e| ParseError::marked(&e, eof_line, eof_col))?;
         if let Err(e) = fs::write(
             "pdp_out/token_stream.txt",
             format!("{token_stream:#?}").as_bytes(),
@@ -154,9 +213,10 @@ impl Parser {
             eprintln!("Warning: couldn't output token stream: {e}");
             warn!("couldn't output token stream: {e}");
         }
+        let token_stream = lex.drop_comments();
 
         info!("Generating concrete parse tree and AST");
-        let parse_results = tpg::parse_tokens(token_stream)?;
+        let parse_results = tpg::parse_tokens(token_stream, self.max_nesting_depth)?;
         if let Err(e) = fs::write(
             "pdp_out/parse_tree.txt",
             format!("{:#?}", parse_results.parse_node).as_bytes(),
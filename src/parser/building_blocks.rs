@@ -14,8 +14,10 @@ pub enum Token {
     STRING(String, usize, usize),
     NUMBER(f64, usize, usize),
     BOOL(bool, usize, usize),
+    NONE(usize, usize),
     NEWLINE(usize, usize),
     MISC(char, usize, usize),
+    COMMENT(String, usize, usize),
     END,
 }
 
@@ -32,8 +34,10 @@ impl Token {
             STRING(_, line, col) => (*line, *col),
             NUMBER(_, line, col) => (*line, *col),
             BOOL(_, line, col) => (*line, *col),
+            NONE(line, col) => (*line, *col),
             NEWLINE(line, col) => (*line, *col),
             MISC(_, line, col) => (*line, *col),
+            COMMENT(_, line, col) => (*line, *col),
             END => (0, 0),
         }
     }
@@ -117,10 +121,7 @@ impl Op {
     }
 
     pub fn negates_dunderscore(&self) -> bool {
-        match self {
-            Op::NotIn | Op::Neq | Op::Not => true,
-            _ => false,
-        }
+        matches!(self, Op::NotIn | Op::Neq | Op::Not)
     }
 
     pub fn dunderscore_method_unary(&self) -> &'static str {
@@ -129,6 +130,37 @@ impl Op {
             _ => self.dunderscore_method(),
         }
     }
+
+    /// How the operator is written in source code.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Op::Plus => "+",
+            Op::Minus => "-",
+            Op::Mult => "*",
+            Op::Div => "/",
+            Op::IntDiv => "//",
+            Op::Mod => "%",
+            Op::Exp => "**",
+            Op::Eq => "==",
+            Op::Neq => "!=",
+            Op::Gt => ">",
+            Op::Gte => ">=",
+            Op::Lt => "<",
+            Op::Lte => "<=",
+            Op::And => "and",
+            Op::Or => "or",
+            Op::Not => "not",
+            Op::BWAnd => "&",
+            Op::BWOr => "|",
+            Op::BWNot => "~",
+            Op::Xor => "^",
+            Op::ShLeft => "<<",
+            Op::ShRight => ">>",
+            Op::In => "in",
+            Op::NotIn => "not in",
+            Op::Identity => "",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -147,12 +179,13 @@ pub enum Asop {
     XorAssign,     // ^=
     ShLeftAssign,  // <<=
     ShRightAssign, // >>=
+    Walrus,        // :=
 }
 
 impl Asop {
     pub fn as_op(&self) -> Op {
         match self {
-            Asop::Assign => Op::Identity,
+            Asop::Assign | Asop::Walrus => Op::Identity,
             Asop::AddAssign => Op::Plus,
             Asop::SubAssign => Op::Minus,
             Asop::MultAssign => Op::Mult,
@@ -172,6 +205,27 @@ impl Asop {
     pub fn dunderscore_method(&self) -> &'static str {
         self.as_op().dunderscore_method()
     }
+
+    /// How the operator is written in source code.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Asop::Assign => "=",
+            Asop::AddAssign => "+=",
+            Asop::SubAssign => "-=",
+            Asop::MultAssign => "*=",
+            Asop::DivAssign => "/=",
+            Asop::ModAssign => "%=",
+            Asop::IntDivAssign => "//=",
+            Asop::ExpAssign => "**=",
+            Asop::BWAndAssign => "&=",
+            Asop::BWOrAssign => "|=",
+            Asop::BWNotAssign => "~=",
+            Asop::XorAssign => "^=",
+            Asop::ShLeftAssign => "<<=",
+            Asop::ShRightAssign => ">>=",
+            Asop::Walrus => ":=",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
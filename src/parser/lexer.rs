@@ -23,9 +23,11 @@ impl StartsWithStr for &[char] {
 #[derive(Debug, Default)]
 pub struct Lexer {
     finished: bool,
+    keep_comments: bool,
     tokens: Vec<Token>,
     next_start_line: usize,
     next_start_col: usize,
+    bracket_depth: usize,
 }
 
 impl Lexer {
@@ -33,10 +35,33 @@ impl Lexer {
         Self::default()
     }
 
+    /// Keep `#` comments in the token stream as `COMMENT` tokens instead of discarding them. This is meant for
+    /// tooling, since the grammar has no place for comments, see `drop_comments()`.
+    pub fn with_comments(mut self) -> Self {
+        self.keep_comments = true;
+        self
+    }
+
+    /// Removes any `COMMENT` tokens from the finished token stream, so that it can be handed to the parser.
+    pub fn drop_comments(&mut self) -> &Vec<Token> {
+        self.tokens.retain(|t| !matches!(t, Token::COMMENT(..)));
+        &self.tokens
+    }
+
+    /// Ends the token stream. Fails if the stream can only be an incomplete program, since the grammar would otherwise
+    /// reject it with an error about whichever token happens to come last.
     pub fn finalize(&mut self) -> Result<&Vec<Token>, String> {
         if self.finished {
             return Err("this lexer has finished its job".to_string());
-        } else if let Token::NEWLINE(_, _) = self.tokens.last().unwrap_or(&Token::END) {
+        }
+
+        if let Some(bracket) = self.unclosed_bracket() {
+            return Err(format!(
+                "unexpected end of file, `{bracket}` was never closed"
+            ));
+        }
+
+        if let Token::NEWLINE(_, _) = self.tokens.last().unwrap_or(&Token::END) {
             // Don't push another newline if there already is one
             self.tokens.push(Token::END);
         } else {
@@ -50,6 +75,21 @@ impl Lexer {
         Ok(&self.tokens)
     }
 
+    /// The innermost bracket that is still open, ignoring mismatched closing brackets since the grammar reports those.
+    fn unclosed_bracket(&self) -> Option<char> {
+        let mut open = Vec::new();
+        for token in &self.tokens {
+            match token {
+                Token::BRACKET(c @ ('(' | '[' | '{'), _, _) => open.push(*c),
+                Token::BRACKET(c, _, _) if open.last().copied() == Some(matching_bracket(*c)) => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+        open.pop()
+    }
+
     /// Used to advance a character iterator by lexeme. It identifies the lexeme, appends its lexed `Token` value to
     /// `self.tokens`, and returns how many characters the iterator was advanced by.
     ///
@@ -59,8 +99,9 @@ impl Lexer {
             return Err("this lexer has finished its job".to_string());
         }
 
-        // Start all lines with an INDENT token, even if the amount is 0
+        // Start all lines with an INDENT token, even if the amount is 0. Lines continuing inside brackets don't count.
         if self.next_start_col == 0
+            && self.bracket_depth == 0
             && !line.is_empty()
             && line[0] != ' '
             && !line.starts_with_str("#")
@@ -71,13 +112,10 @@ impl Lexer {
         // == Actual tokenization logic starts here == //
         if line.is_empty() {
             // newline
-            self.tokens
-                .push(Token::NEWLINE(self.next_start_line, self.next_start_col));
-            self.next_start_col = 0;
-            self.next_start_line += 1;
+            self.end_line();
             Ok(1)
         } else if line[0] == ' ' {
-            if self.next_start_col == 0 {
+            if self.next_start_col == 0 && self.bracket_depth == 0 {
                 // Count indentation spaces at the start of a line
                 let mut num_spaces = 0;
 
@@ -87,10 +125,8 @@ impl Lexer {
                         num_spaces += 1;
                     } else if *c == '#' {
                         // We don't care about indentations if the line is only a comment
-                        self.tokens
-                            .push(Token::NEWLINE(self.next_start_line, self.next_start_col));
-                        self.next_start_line += 1;
-                        self.next_start_col = 0;
+                        self.push_comment(&line[num_spaces..], num_spaces);
+                        self.end_line();
                         return Ok(line.len() + 1);
                     } else {
                         break;
@@ -123,10 +159,8 @@ impl Lexer {
                         num_spaces += 1;
                     } else if *c == '#' {
                         // Ignore the rest of the line if the spaces are followed by a comment
-                        self.tokens
-                            .push(Token::NEWLINE(self.next_start_line, self.next_start_col));
-                        self.next_start_line += 1;
-                        self.next_start_col = 0;
+                        self.push_comment(&line[num_spaces..], self.next_start_col + num_spaces);
+                        self.end_line();
                         return Ok(line.len() + 1);
                     } else {
                         break;
@@ -138,10 +172,8 @@ impl Lexer {
             }
         } else if line.starts_with_str("#") {
             // Ignore the rest of the line and push a NEWLINE
-            self.tokens
-                .push(Token::NEWLINE(self.next_start_line, self.next_start_col));
-            self.next_start_col = 0;
-            self.next_start_line += 1;
+            self.push_comment(line, self.next_start_col);
+            self.end_line();
             Ok(0)
         } else if line.starts_with_str("if") && Self::word_boundary(line, 2) {
             self.tokens.push(Token::KEYWORD(
@@ -212,6 +244,11 @@ impl Lexer {
             ));
             self.next_start_col += 5;
             Ok(5)
+        } else if line.starts_with_str("None") && Self::word_boundary(line, 4) {
+            self.tokens
+                .push(Token::NONE(self.next_start_line, self.next_start_col));
+            self.next_start_col += 4;
+            Ok(4)
         } else if line.starts_with_str("and") && Self::word_boundary(line, 3) {
             self.tokens.push(Token::OP(
                 Op::And,
@@ -350,6 +387,14 @@ impl Lexer {
             ));
             self.next_start_col += 3;
             Ok(3)
+        } else if line.starts_with_str(":=") {
+            self.tokens.push(Token::ASOP(
+                Asop::Walrus,
+                self.next_start_line,
+                self.next_start_col,
+            ));
+            self.next_start_col += 2;
+            Ok(2)
         } else if line.starts_with_str("+") {
             self.tokens.push(Token::OP(
                 Op::Plus,
@@ -469,11 +514,6 @@ impl Lexer {
                 .push(Token::OP(Op::Gt, self.next_start_line, self.next_start_col));
             self.next_start_col += 1;
             Ok(1)
-        } else if line.starts_with_str("==") {
-            self.tokens
-                .push(Token::OP(Op::Eq, self.next_start_line, self.next_start_col));
-            self.next_start_col += 2;
-            Ok(2)
         } else if line.starts_with_str("&") {
             self.tokens.push(Token::OP(
                 Op::BWAnd,
@@ -507,6 +547,7 @@ impl Lexer {
             self.next_start_col += 1;
             Ok(1)
         } else if line.starts_with_str("(") {
+            self.bracket_depth += 1;
             self.tokens.push(Token::BRACKET(
                 '(',
                 self.next_start_line,
@@ -515,6 +556,7 @@ impl Lexer {
             self.next_start_col += 1;
             Ok(1)
         } else if line.starts_with_str(")") {
+            self.bracket_depth = self.bracket_depth.saturating_sub(1);
             self.tokens.push(Token::BRACKET(
                 ')',
                 self.next_start_line,
@@ -523,6 +565,7 @@ impl Lexer {
             self.next_start_col += 1;
             Ok(1)
         } else if line.starts_with_str("[") {
+            self.bracket_depth += 1;
             self.tokens.push(Token::BRACKET(
                 '[',
                 self.next_start_line,
@@ -531,6 +574,7 @@ impl Lexer {
             self.next_start_col += 1;
             Ok(1)
         } else if line.starts_with_str("]") {
+            self.bracket_depth = self.bracket_depth.saturating_sub(1);
             self.tokens.push(Token::BRACKET(
                 ']',
                 self.next_start_line,
@@ -539,6 +583,7 @@ impl Lexer {
             self.next_start_col += 1;
             Ok(1)
         } else if line.starts_with_str("{") {
+            self.bracket_depth += 1;
             self.tokens.push(Token::BRACKET(
                 '{',
                 self.next_start_line,
@@ -547,6 +592,7 @@ impl Lexer {
             self.next_start_col += 1;
             Ok(1)
         } else if line.starts_with_str("}") {
+            self.bracket_depth = self.bracket_depth.saturating_sub(1);
             self.tokens.push(Token::BRACKET(
                 '}',
                 self.next_start_line,
@@ -603,7 +649,18 @@ impl Lexer {
                     // Find first non-escaped matching quote
                     if escaped {
                         escaped = false;
-                        result_str.push(line[idx]);
+                        match line[idx] {
+                            'n' => result_str.push('\n'),
+                            't' => result_str.push('\t'),
+                            'r' => result_str.push('\r'),
+                            '0' => result_str.push('\0'),
+                            c @ ('\\' | '\'' | '"') => result_str.push(c),
+                            // Like in Python, an unknown escape sequence is kept as it was written
+                            c => {
+                                result_str.push('\\');
+                                result_str.push(c);
+                            }
+                        }
                     } else if line[idx] == '\\' {
                         escaped = true;
                     } else {
@@ -627,7 +684,7 @@ impl Lexer {
             ));
             self.next_start_col += idx + 1;
             Ok(idx + 1)
-        } else if line[0].is_ascii_alphabetic() {
+        } else if line[0].is_ascii_alphabetic() || line[0] == '_' {
             // name
 
             let mut idx = 1;
@@ -645,6 +702,9 @@ impl Lexer {
             ));
             self.next_start_col += idx;
             Ok(idx)
+        } else if !line[0].is_ascii() && line[0].is_alphanumeric() {
+            // Names are ASCII only, so a name with a non-ASCII letter stops right before it and ends up here
+            Err(format!("invalid character in identifier '{}'", line[0]))
         } else {
             // misc
 
@@ -659,6 +719,28 @@ impl Lexer {
         // == Tokenization logic ends here == //
     }
 
+    /// Ends the current line, which only ends the logical line outside of brackets. Inside them it continues implicitly
+    /// like in Python, so there's no NEWLINE.
+    fn end_line(&mut self) {
+        if self.bracket_depth == 0 {
+            self.tokens
+                .push(Token::NEWLINE(self.next_start_line, self.next_start_col));
+        }
+        self.next_start_line += 1;
+        self.next_start_col = 0;
+    }
+
+    /// Push a `COMMENT` token for `comment`, which must start at its `#`, if comments are being kept.
+    fn push_comment(&mut self, comment: &[char], col: usize) {
+        if self.keep_comments {
+            self.tokens.push(Token::COMMENT(
+                comment[1..].iter().collect(),
+                self.next_start_line,
+                col,
+            ));
+        }
+    }
+
     fn word_boundary(line: &[char], idx: usize) -> bool {
         idx >= line.len() || (line[idx] != '_' && !line[idx].is_ascii_alphanumeric())
     }
@@ -668,6 +750,14 @@ impl Lexer {
     }
 }
 
+fn matching_bracket(closing: char) -> char {
+    match closing {
+        ')' => '(',
+        ']' => '[',
+        _ => '{',
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1004,11 +1094,254 @@ mod tests {
             Some(&Token::NAME("var".to_string(), 0, 0))
         );
         assert_eq!(token_stream.next(), Some(&Token::MISC('.', 0, 3)));
+
+        // `None` is a literal, but only as a whole word
+        let mut lexer = Lexer::new();
+        let py_line = char_slice!("None Nonesuch");
+        lexer.identify(py_line).unwrap();
+        lexer.identify(&py_line[4..]).unwrap();
+        lexer.identify(&py_line[5..]).unwrap();
+        let mut token_stream = lexer.finalize().unwrap().iter();
+        token_stream.next(); // First token is an empty INDENT
+        assert_eq!(token_stream.next(), Some(&Token::NONE(0, 0)));
+        assert_eq!(
+            token_stream.next(),
+            Some(&Token::NAME("Nonesuch".to_string(), 0, 5))
+        );
+
+        // With a non-ASCII letter
+        let mut lexer = Lexer::new();
+        let py_line = char_slice!("café = 1");
+        assert_eq!(lexer.identify(py_line), Ok(3));
+        assert_eq!(
+            lexer.identify(&py_line[3..]).unwrap_err(),
+            "invalid character in identifier 'é'".to_string()
+        );
+
+        // Reported at the offending column, even though the line is longer in bytes than in chars
+        let err = crate::parser::Parser::new()
+            .parse_from_str("x = 1\nnaïve = 2\n")
+            .err()
+            .unwrap();
+        assert_eq!(err.msg, "invalid character in identifier 'ï'");
+        assert!(err.to_string().contains("(unset:2:3)"));
+        assert!(
+            crate::parser::Parser::new()
+                .parse_from_str("s = \"né\"\n")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_lexer_comments() {
+        // Discarded by default
+        let mut lexer = Lexer::new();
+        let py_line = char_slice!("# only a comment");
+        assert_eq!(lexer.identify(py_line), Ok(0));
+        let mut token_stream = lexer.finalize().unwrap().iter();
+        assert_eq!(token_stream.next(), Some(&Token::NEWLINE(0, 0)));
+
+        // Trailing comment, kept
+        let mut lexer = Lexer::new().with_comments();
+        let py_line = char_slice!("x  # the answer");
+        assert_eq!(lexer.identify(py_line), Ok(1)); // `indent(0) x`
+        assert_eq!(lexer.identify(&py_line[1..]), Ok(py_line.len())); // `  # the answer` and newline
+        let mut token_stream = lexer.finalize().unwrap().iter();
+        token_stream.next(); // First token is an empty INDENT
+        token_stream.next(); // `x`
+        assert_eq!(
+            token_stream.next(),
+            Some(&Token::COMMENT(" the answer".to_string(), 0, 3))
+        );
+        assert_eq!(token_stream.next(), Some(&Token::NEWLINE(0, 1)));
+
+        // Indented comment-only line, kept
+        let mut lexer = Lexer::new().with_comments();
+        let py_line = char_slice!("    #todo");
+        lexer.identify(py_line).unwrap();
+        let mut token_stream = lexer.finalize().unwrap().iter();
+        assert_eq!(
+            token_stream.next(),
+            Some(&Token::COMMENT("todo".to_string(), 0, 4))
+        );
+        assert_eq!(token_stream.next(), Some(&Token::NEWLINE(0, 0)));
+    }
+
+    #[test]
+    fn test_lexer_implicit_line_continuation() {
+        let mut lexer = Lexer::new();
+        for line in ["x = [1,  # one", "  # nothing here", "", "     2]", "y"] {
+            let line = char_slice!(line);
+            let mut col = 0;
+            while col <= line.len() {
+                match lexer.identify(&line[col..]).unwrap() {
+                    0 => break,
+                    n => col += n,
+                }
+            }
+        }
+        let token_stream = lexer.finalize().unwrap();
+
+        // No NEWLINE or INDENT until the list is closed, whatever the lines inside it look like
+        assert_eq!(
+            token_stream[..9],
+            [
+                Token::INDENT(0, 0, 0),
+                Token::NAME("x".to_string(), 0, 0),
+                Token::ASOP(Asop::Assign, 0, 2),
+                Token::BRACKET('[', 0, 4),
+                Token::NUMBER(1.0, 0, 5),
+                Token::MISC(',', 0, 6),
+                Token::NUMBER(2.0, 3, 5),
+                Token::BRACKET(']', 3, 6),
+                Token::NEWLINE(3, 7),
+            ]
+        );
+        assert_eq!(token_stream[9], Token::INDENT(0, 4, 0));
+    }
+
+    #[test]
+    fn test_lexer_unexpected_eof() {
+        for (source, err) in [
+            ("def f(", "unexpected end of file, `(` was never closed"),
+            ("x = [1, {2", "unexpected end of file, `{` was never closed"),
+            ("x = [(1)", "unexpected end of file, `[` was never closed"),
+        ] {
+            let mut lexer = Lexer::new();
+            let py_line = char_slice!(source);
+            let mut col = 0;
+            while col <= py_line.len() {
+                col += lexer.identify(&py_line[col..]).unwrap();
+            }
+            assert_eq!(lexer.finalize(), Err(err.to_string()));
+        }
+
+        let err = crate::parser::Parser::new()
+            .parse_from_str("def f(\n")
+            .err()
+            .unwrap();
+        assert_eq!(err.msg, "unexpected end of file, `(` was never closed");
+        assert!(err.to_string().contains("(unset:1:7)"), "{err}");
     }
 
     #[test]
-    #[ignore = "I'm too lazy to test every single token, maybe I'll do it later"]
     fn test_lexer_exhaustive() {
-        todo!();
+        let mut lexer = Lexer::new();
+        for line in [
+            r#"if while for continue break return def"#,
+            r#"True False None and or in not in not"#,
+            r#"= += -= *= /= //= %= **= &= |= ~= ^= <<= >>= :="#,
+            r#"+ - ** * // / % == != << <= < >> >= > & | ^ ~"#,
+            r#"( ) [ ] { }"#,
+            r#"name _private x1 12 3.5 "a\n" 'b\'c' "\d" , : . !"#,
+            r#"a==b<=c//d**e"#,
+            r#"    indented"#,
+        ] {
+            let line = char_slice!(line);
+            let mut col = 0;
+            while col <= line.len() {
+                col += lexer.identify(&line[col..]).unwrap();
+            }
+        }
+
+        assert_eq!(
+            lexer.finalize().unwrap(),
+            &vec![
+                Token::INDENT(0, 0, 0),
+                Token::KEYWORD(Keyword::If, 0, 0),
+                Token::KEYWORD(Keyword::While, 0, 3),
+                Token::KEYWORD(Keyword::For, 0, 9),
+                Token::KEYWORD(Keyword::Continue, 0, 13),
+                Token::KEYWORD(Keyword::Break, 0, 22),
+                Token::KEYWORD(Keyword::Return, 0, 28),
+                Token::KEYWORD(Keyword::Def, 0, 35),
+                Token::NEWLINE(0, 38),
+                Token::INDENT(0, 1, 0),
+                Token::BOOL(true, 1, 0),
+                Token::BOOL(false, 1, 5),
+                Token::NONE(1, 11),
+                Token::OP(Op::And, 1, 16),
+                Token::OP(Op::Or, 1, 20),
+                Token::OP(Op::In, 1, 23),
+                Token::OP(Op::NotIn, 1, 26),
+                Token::OP(Op::Not, 1, 33),
+                Token::NEWLINE(1, 36),
+                Token::INDENT(0, 2, 0),
+                Token::ASOP(Asop::Assign, 2, 0),
+                Token::ASOP(Asop::AddAssign, 2, 2),
+                Token::ASOP(Asop::SubAssign, 2, 5),
+                Token::ASOP(Asop::MultAssign, 2, 8),
+                Token::ASOP(Asop::DivAssign, 2, 11),
+                Token::ASOP(Asop::IntDivAssign, 2, 14),
+                Token::ASOP(Asop::ModAssign, 2, 18),
+                Token::ASOP(Asop::ExpAssign, 2, 21),
+                Token::ASOP(Asop::BWAndAssign, 2, 25),
+                Token::ASOP(Asop::BWOrAssign, 2, 28),
+                Token::ASOP(Asop::BWNotAssign, 2, 31),
+                Token::ASOP(Asop::XorAssign, 2, 34),
+                Token::ASOP(Asop::ShLeftAssign, 2, 37),
+                Token::ASOP(Asop::ShRightAssign, 2, 41),
+                Token::ASOP(Asop::Walrus, 2, 45),
+                Token::NEWLINE(2, 47),
+                Token::INDENT(0, 3, 0),
+                Token::OP(Op::Plus, 3, 0),
+                Token::OP(Op::Minus, 3, 2),
+                Token::OP(Op::Exp, 3, 4),
+                Token::OP(Op::Mult, 3, 7),
+                Token::OP(Op::IntDiv, 3, 9),
+                Token::OP(Op::Div, 3, 12),
+                Token::OP(Op::Mod, 3, 14),
+                Token::OP(Op::Eq, 3, 16),
+                Token::OP(Op::Neq, 3, 19),
+                Token::OP(Op::ShLeft, 3, 22),
+                Token::OP(Op::Lte, 3, 25),
+                Token::OP(Op::Lt, 3, 28),
+                Token::OP(Op::ShRight, 3, 30),
+                Token::OP(Op::Gte, 3, 33),
+                Token::OP(Op::Gt, 3, 36),
+                Token::OP(Op::BWAnd, 3, 38),
+                Token::OP(Op::BWOr, 3, 40),
+                Token::OP(Op::Xor, 3, 42),
+                Token::OP(Op::BWNot, 3, 44),
+                Token::NEWLINE(3, 45),
+                Token::INDENT(0, 4, 0),
+                Token::BRACKET('(', 4, 0),
+                Token::BRACKET(')', 4, 2),
+                Token::BRACKET('[', 4, 4),
+                Token::BRACKET(']', 4, 6),
+                Token::BRACKET('{', 4, 8),
+                Token::BRACKET('}', 4, 10),
+                Token::NEWLINE(4, 11),
+                Token::INDENT(0, 5, 0),
+                Token::NAME("name".to_string(), 5, 0),
+                Token::NAME("_private".to_string(), 5, 5),
+                Token::NAME("x1".to_string(), 5, 14),
+                Token::NUMBER(12.0, 5, 17),
+                Token::NUMBER(3.5, 5, 20),
+                Token::STRING("a\n".to_string(), 5, 24),
+                Token::STRING("b'c".to_string(), 5, 30),
+                Token::STRING("\\d".to_string(), 5, 37),
+                Token::MISC(',', 5, 42),
+                Token::MISC(':', 5, 44),
+                Token::MISC('.', 5, 46),
+                Token::MISC('!', 5, 48),
+                Token::NEWLINE(5, 49),
+                Token::INDENT(0, 6, 0),
+                Token::NAME("a".to_string(), 6, 0),
+                Token::OP(Op::Eq, 6, 1),
+                Token::NAME("b".to_string(), 6, 3),
+                Token::OP(Op::Lte, 6, 4),
+                Token::NAME("c".to_string(), 6, 6),
+                Token::OP(Op::IntDiv, 6, 7),
+                Token::NAME("d".to_string(), 6, 9),
+                Token::OP(Op::Exp, 6, 10),
+                Token::NAME("e".to_string(), 6, 12),
+                Token::NEWLINE(6, 13),
+                Token::INDENT(1, 7, 0),
+                Token::NAME("indented".to_string(), 7, 4),
+                Token::NEWLINE(7, 12),
+                Token::END,
+            ]
+        );
     }
 }
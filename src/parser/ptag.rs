@@ -7,13 +7,17 @@ use super::markers::*;
 macro_rules! identity_safe_ast {
     () => {
         $crate::parser::ptag::AstNode::function_call { .. }
+            | $crate::parser::ptag::AstNode::call { .. }
             | $crate::parser::ptag::AstNode::variable { .. }
+            | $crate::parser::ptag::AstNode::subscript { .. }
+            | $crate::parser::ptag::AstNode::named_expr { .. }
             | $crate::parser::ptag::AstNode::list(..)
             | $crate::parser::ptag::AstNode::dictionary(..)
             | $crate::parser::ptag::AstNode::set(..)
             | $crate::parser::ptag::AstNode::string(..)
             | $crate::parser::ptag::AstNode::number(..)
             | $crate::parser::ptag::AstNode::boolean(..)
+            | $crate::parser::ptag::AstNode::none
     };
 }
 
@@ -31,6 +35,7 @@ macro_rules! non_identity_ast {
             | AstNode::arguments(_)
             | AstNode::assign_op { .. }
             | AstNode::assign_op_rhs { .. }
+            | AstNode::subscript_assign_op { .. }
             | AstNode::binary_op_rhs { .. }
             | AstNode::block(_)
             | AstNode::r#break
@@ -72,6 +77,7 @@ pub enum AstNode {
     string(MarkedString),
     number(MarkedNumber),
     boolean(MarkedBoolean),
+    none,
     misc(MarkedComponent<char>),
 
     // meta nodes
@@ -83,11 +89,14 @@ pub enum AstNode {
     assign_op {
         variable: MarkedString,
         accesses: Vec<MarkedOperationTree>,
+        /// The targets after the first in `a = b[0] = value`, from left to right.
+        chained: Vec<(MarkedString, Vec<MarkedOperationTree>)>,
         asop: MarkedAsop,
         value: Box<MarkedOperationTree>,
     },
     assign_op_rhs {
-        accesses: Vec<MarkedOperationTree>,
+        postfixes: Vec<MarkedAstNode>,
+        chained: Vec<(MarkedString, Vec<MarkedOperationTree>)>,
         asop: MarkedAsop,
         rhs: Box<MarkedOperationTree>,
     },
@@ -97,6 +106,10 @@ pub enum AstNode {
     },
     block(Vec<MarkedAstNode>),
     r#break,
+    call {
+        callee: Box<MarkedOperationTree>,
+        arguments: Vec<MarkedOperationTree>,
+    },
     r#continue,
     dictionary(Vec<(MarkedString, MarkedOperationTree)>),
     empty,
@@ -120,9 +133,25 @@ pub enum AstNode {
         then: Box<MarkedAstNode>,
     },
     list(Vec<MarkedOperationTree>),
+    named_expr {
+        target: MarkedString,
+        value: Box<MarkedOperationTree>,
+    },
     parameters(Vec<MarkedString>),
     return_stmt(Option<Box<MarkedOperationTree>>),
     set(Vec<MarkedOperationTree>),
+    subscript {
+        value: Box<MarkedOperationTree>,
+        accesses: Vec<MarkedOperationTree>,
+    },
+    /// An assignment to an index of something that isn't a plain variable, like `f()[0] = value`.
+    subscript_assign_op {
+        container: Box<MarkedOperationTree>,
+        access: Box<MarkedOperationTree>,
+        chained: Vec<(MarkedString, Vec<MarkedOperationTree>)>,
+        asop: MarkedAsop,
+        value: Box<MarkedOperationTree>,
+    },
     variable {
         identifier: MarkedString,
         accesses: Vec<MarkedOperationTree>,
@@ -149,6 +178,139 @@ macro_rules! tuplify {
 }
 
 impl AstNode {
+    /// Turns a `multiple` of statements into a `block`, dropping the `empty`s that blank lines leave behind.
+    fn statement_block(node: MarkedAstNode) -> MarkedAstNode {
+        let mark = node.mark;
+        let statements = tuplify!(node, multiple)
+            .into_iter()
+            .filter(|statement| !matches!(statement.comp, Self::empty))
+            .collect();
+        MarkedAstNode::new(Self::block(statements), mark)
+    }
+
+    /// Applies a chain of `arguments` and `access` nodes to the variable `name`, from left to right.
+    ///
+    /// Plain calls and indexes of a name keep their simpler `function_call` and `variable` forms.
+    fn apply_postfixes(name: MarkedAstNode, postfixes: Vec<MarkedAstNode>) -> MarkedAstNode {
+        let mark = name.mark;
+        let start = MarkedAstNode::new(
+            Self::variable {
+                identifier: tuplify!(name, name),
+                accesses: Vec::new(),
+            },
+            mark,
+        );
+
+        postfixes.into_iter().fold(start, |prev, postfix| {
+            let comp = match (prev.comp, postfix.comp) {
+                (
+                    Self::variable {
+                        identifier,
+                        accesses,
+                    },
+                    Self::arguments(arguments),
+                ) if accesses.is_empty() => Self::function_call {
+                    function: identifier,
+                    arguments,
+                },
+                (
+                    Self::variable {
+                        identifier,
+                        mut accesses,
+                    },
+                    Self::access(access),
+                ) => {
+                    accesses.extend(access);
+                    Self::variable {
+                        identifier,
+                        accesses,
+                    }
+                }
+                (
+                    Self::subscript {
+                        value,
+                        mut accesses,
+                    },
+                    Self::access(access),
+                ) => {
+                    accesses.extend(access);
+                    Self::subscript { value, accesses }
+                }
+                (callee, Self::arguments(arguments)) => Self::call {
+                    callee: Box::new(MarkedOperationTree::new(
+                        OperationTree::Identity(MarkedAstNode::new(callee, mark)),
+                        mark,
+                    )),
+                    arguments,
+                },
+                (value, Self::access(accesses)) => Self::subscript {
+                    value: Box::new(MarkedOperationTree::new(
+                        OperationTree::Identity(MarkedAstNode::new(value, mark)),
+                        mark,
+                    )),
+                    accesses,
+                },
+                (_, bad) => panic!("Tried applying {bad:?} as a postfix"),
+            };
+            MarkedAstNode::new(comp, mark)
+        })
+    }
+
+    /// The assignment to `name` followed by `postfixes`, which the parser has already checked don't end in a call.
+    /// Without any calls, that's a variable and its accesses. Otherwise everything before the last access is evaluated
+    /// as an expression and only that access is assigned to.
+    fn assign_to_postfixes(
+        name: MarkedAstNode,
+        postfixes: Vec<MarkedAstNode>,
+        chained: Vec<(MarkedString, Vec<MarkedOperationTree>)>,
+        asop: MarkedAsop,
+        value: Box<MarkedOperationTree>,
+    ) -> MarkedAstNode {
+        let mark = name.mark;
+        let comp = match Self::apply_postfixes(name, postfixes).comp {
+            Self::variable {
+                identifier,
+                accesses,
+            } => Self::assign_op {
+                variable: identifier,
+                accesses,
+                chained,
+                asop,
+                value,
+            },
+            Self::subscript {
+                value: subscripted,
+                mut accesses,
+            } => {
+                // Unwrap is safe because a subscript always has at least one access
+                let access = accesses.pop().unwrap();
+                let container = if accesses.is_empty() {
+                    subscripted
+                } else {
+                    Box::new(MarkedOperationTree::new(
+                        OperationTree::Identity(MarkedAstNode::new(
+                            Self::subscript {
+                                value: subscripted,
+                                accesses,
+                            },
+                            mark,
+                        )),
+                        mark,
+                    ))
+                };
+                Self::subscript_assign_op {
+                    container,
+                    access: Box::new(access),
+                    chained,
+                    asop,
+                    value,
+                }
+            }
+            bad => panic!("Tried assigning to {bad:?}"),
+        };
+        MarkedAstNode::new(comp, mark)
+    }
+
     /// ```
     /// Index: expr ⟶ access
     /// ```
@@ -243,70 +405,39 @@ impl AstNode {
     }
 
     /// ```
-    /// NameExpr.1: empty ⟶ arguments
-    ///             expr+ ⟶ arguments
+    /// NameExpr: (arguments|access)* ⟶ multiple
     /// ```
-    pub fn from_name_expr_1(first: MarkedAstNode) -> MarkedAstNode {
+    pub fn from_name_expr(first: MarkedAstNode) -> MarkedAstNode {
+        first
+    }
+
+    /// ```
+    /// Postfix.1: empty ⟶ arguments
+    ///            expr+ ⟶ arguments
+    /// ```
+    pub fn from_postfix_1(first: MarkedAstNode) -> MarkedAstNode {
         match first.comp {
             Self::empty => MarkedAstNode::new(Self::arguments(Vec::new()), first.mark),
             Self::multiple(exprs) => MarkedAstNode::new(
                 Self::arguments(exprs.into_iter().map(|e| *tuplify!(e, expr)).collect()),
                 first.mark,
             ),
-            bad => panic!("Tried calling from_name_expr_1() with {bad:?}"),
+            bad => panic!("Tried calling from_postfix_1() with {bad:?}"),
         }
     }
 
     /// ```
-    /// NameExpr.2: empty   ⟶ empty
-    ///             access+ ⟶ access
+    /// Postfix.2: access
     /// ```
-    pub fn from_name_expr_2(first: MarkedAstNode) -> MarkedAstNode {
-        match first.comp {
-            Self::empty => first,
-            Self::multiple(accesses) => MarkedAstNode::new(
-                Self::access(
-                    accesses
-                        .into_iter()
-                        .map(|a| tuplify!(a, access).into_iter().next().unwrap())
-                        .collect(),
-                ),
-                first.mark,
-            ),
-            bad => panic!("Tried calling from_name_expr_2() with {bad:?}"),
-        }
+    pub fn from_postfix_2(first: MarkedAstNode) -> MarkedAstNode {
+        first
     }
 
     /// ```
-    /// ExprUnit.1: name arguments ⟶ function_call
-    ///             name empty     ⟶ variable
-    ///             name access    ⟶ variable
+    /// ExprUnit.1: name multiple ⟶ function_call|variable|call|subscript
     /// ```
     pub fn from_expr_unit_1(first: MarkedAstNode, second: MarkedAstNode) -> MarkedAstNode {
-        match second.comp {
-            Self::arguments(args) => MarkedAstNode::new(
-                Self::function_call {
-                    function: tuplify!(first, name),
-                    arguments: args,
-                },
-                first.mark,
-            ),
-            Self::empty => MarkedAstNode::new(
-                Self::variable {
-                    identifier: tuplify!(first, name),
-                    accesses: Vec::new(),
-                },
-                first.mark,
-            ),
-            Self::access(accesses) => MarkedAstNode::new(
-                Self::variable {
-                    identifier: tuplify!(first, name),
-                    accesses,
-                },
-                first.mark,
-            ),
-            bad => panic!("Tried calling from_expr_unit_1() with {bad:?}"),
-        }
+        Self::apply_postfixes(first, tuplify!(second, multiple))
     }
 
     /// ```
@@ -366,6 +497,13 @@ impl AstNode {
         first
     }
 
+    /// ```
+    /// ExprUnit.8: none
+    /// ```
+    pub fn from_expr_unit_8(first: MarkedAstNode) -> MarkedAstNode {
+        first
+    }
+
     /// ```
     /// ExprBinary: op function_call ⟶ binary_op_rhs
     ///             op variable      ⟶ binary_op_rhs
@@ -395,6 +533,34 @@ impl AstNode {
         )
     }
 
+    /// ```
+    /// NamedExpr.1: name expr ⟶ expr
+    /// ```
+    pub fn from_named_expr_1(first: MarkedAstNode, second: MarkedAstNode) -> MarkedAstNode {
+        let mark = first.mark;
+        let named_expr = MarkedAstNode::new(
+            Self::named_expr {
+                target: tuplify!(first, name),
+                value: tuplify!(second, expr),
+            },
+            mark,
+        );
+        MarkedAstNode::new(
+            Self::expr(Box::new(MarkedOperationTree::new(
+                OperationTree::Identity(named_expr),
+                mark,
+            ))),
+            mark,
+        )
+    }
+
+    /// ```
+    /// NamedExpr.2: expr
+    /// ```
+    pub fn from_named_expr_2(first: MarkedAstNode) -> MarkedAstNode {
+        first
+    }
+
     /// ```
     /// ExprUnary.1: function_call ⟶ expr
     ///              variable      ⟶ expr
@@ -547,47 +713,79 @@ impl AstNode {
     }
 
     /// ```
-    /// SideEffect.1: empty ⟶ arguments
-    ///               expr+ ⟶ arguments
+    /// SideEffect: multiple empty         ⟶ multiple
+    ///             multiple assign_op_rhs ⟶ assign_op_rhs
     /// ```
-    pub fn from_side_effect_1(first: MarkedAstNode) -> MarkedAstNode {
-        match first.comp {
-            Self::empty => MarkedAstNode::new(Self::arguments(Vec::new()), first.mark),
-            Self::multiple(exprs) => MarkedAstNode::new(
-                Self::arguments(exprs.into_iter().map(|e| *tuplify!(e, expr)).collect()),
-                first.mark,
+    pub fn from_side_effect(first: MarkedAstNode, second: MarkedAstNode) -> MarkedAstNode {
+        match second.comp {
+            Self::empty => first,
+            Self::assign_op_rhs {
+                chained, asop, rhs, ..
+            } => MarkedAstNode::new(
+                Self::assign_op_rhs {
+                    postfixes: tuplify!(first, multiple),
+                    chained,
+                    asop,
+                    rhs,
+                },
+                second.mark,
             ),
-            bad => panic!("Tried calling from_side_effect_1() with {bad:?}"),
+            bad => panic!("Tried calling from_side_effect() with {bad:?}"),
         }
     }
 
     /// ```
-    /// SideEffect.2: access* asop expr ⟶ assign_op_rhs
+    /// Assign: asop expr multiple ⟶ assign_op_rhs
     /// ```
-    pub fn from_side_effect_2(
+    ///
+    /// In a chain, every `expr` but the last is another target, which the parser has already checked is a variable.
+    pub fn from_assign(
         first: MarkedAstNode,
         second: MarkedAstNode,
         third: MarkedAstNode,
     ) -> MarkedAstNode {
-        let accesses = tuplify!(first, multiple)
+        let mut exprs = vec![second];
+        exprs.extend(tuplify!(third, multiple));
+        let rhs = tuplify!(exprs.pop().unwrap(), expr);
+
+        let chained = exprs
             .into_iter()
-            .map(|a| tuplify!(a, access).into_iter().next().unwrap())
+            .map(|target| match tuplify!(target, expr).comp {
+                OperationTree::Identity(MarkedAstNode {
+                    comp:
+                        Self::variable {
+                            identifier,
+                            accesses,
+                        },
+                    ..
+                }) => (identifier, accesses),
+                bad => panic!("Tried calling from_assign() with a chained {bad:?}"),
+            })
             .collect();
+
         MarkedAstNode::new(
             Self::assign_op_rhs {
-                accesses,
-                asop: tuplify!(second, asop),
-                rhs: tuplify!(third, expr),
+                postfixes: Vec::new(),
+                chained,
+                asop: tuplify!(first, asop),
+                rhs,
             },
             first.mark,
         )
     }
 
+    /// ```
+    /// ChainedAssign: asop expr ⟶ expr
+    /// ```
+    pub fn from_chained_assign(_first: MarkedAstNode, second: MarkedAstNode) -> MarkedAstNode {
+        second
+    }
+
     /// ```
     /// Body.1: (empty|if_stmt|while_loop|for_loop|continue|break|return_stmt|function_def|function_call|assign_op)* ⟶ block
     /// ```
     pub fn from_body_1(first: MarkedAstNode) -> MarkedAstNode {
-        MarkedAstNode::new(Self::block(tuplify!(first, multiple)), first.mark)
+        Self::statement_block(first)
     }
 
     /// ```
@@ -601,39 +799,52 @@ impl AstNode {
     /// Result.1: (empty|if_stmt|while_loop|for_loop|continue|break|return_stmt|function_def|function_call|assign_op)+ ⟶ block
     /// ```
     pub fn from_result_1(first: MarkedAstNode) -> MarkedAstNode {
-        MarkedAstNode::new(Self::block(tuplify!(first, multiple)), first.mark)
+        Self::statement_block(first)
     }
 
     /// ```
-    /// Result.2: name arguments     ⟶ function_call
-    ///           name assign_op_rhs ⟶ assign_op
+    /// Result.2: name multiple      ⟶ function_call|call
+    ///           name assign_op_rhs ⟶ assign_op|subscript_assign_op
     /// ```
     pub fn from_result_2(first: MarkedAstNode, second: MarkedAstNode) -> MarkedAstNode {
         match second.comp {
-            Self::arguments(args) => MarkedAstNode::new(
-                Self::function_call {
-                    function: tuplify!(first, name),
-                    arguments: args,
-                },
-                first.mark,
-            ),
+            Self::multiple(postfixes) => Self::apply_postfixes(first, postfixes),
             Self::assign_op_rhs {
-                accesses,
+                postfixes,
+                chained,
                 asop,
                 rhs,
-            } => MarkedAstNode::new(
-                Self::assign_op {
-                    variable: tuplify!(first, name),
-                    accesses,
-                    asop,
-                    value: rhs,
-                },
-                first.mark,
-            ),
+            } => Self::assign_to_postfixes(first, postfixes, chained, asop, rhs),
             bad => panic!("Tried calling from_result_2() with {bad:?}"),
         }
     }
 
+    /// ```
+    /// Result.3: continue
+    /// ```
+    pub fn from_result_3(first: MarkedAstNode) -> MarkedAstNode {
+        first
+    }
+
+    /// ```
+    /// Result.4: break
+    /// ```
+    pub fn from_result_4(first: MarkedAstNode) -> MarkedAstNode {
+        first
+    }
+
+    /// ```
+    /// Result.5: empty ⟶ return_stmt
+    ///           expr  ⟶ return_stmt
+    /// ```
+    pub fn from_result_5(first: MarkedAstNode) -> MarkedAstNode {
+        match first.comp {
+            Self::empty => MarkedAstNode::new(Self::return_stmt(None), first.mark),
+            Self::expr(op_tree) => MarkedAstNode::new(Self::return_stmt(Some(op_tree)), first.mark),
+            bad => panic!("Tried calling from_result_5() with {bad:?}"),
+        }
+    }
+
     /// ```
     /// Unit.1: expr function_call ⟶ if_stmt
     ///         expr assign_op     ⟶ if_stmt
@@ -734,31 +945,18 @@ impl AstNode {
     }
 
     /// ```
-    /// Unit.8: name arguments     ⟶ function_call
-    ///         name assign_op_rhs ⟶ assign_op
+    /// Unit.8: name multiple      ⟶ function_call|call
+    ///         name assign_op_rhs ⟶ assign_op|subscript_assign_op
     /// ```
     pub fn from_unit_8(first: MarkedAstNode, second: MarkedAstNode) -> MarkedAstNode {
         match second.comp {
-            Self::arguments(args) => MarkedAstNode::new(
-                Self::function_call {
-                    function: tuplify!(first, name),
-                    arguments: args,
-                },
-                first.mark,
-            ),
+            Self::multiple(postfixes) => Self::apply_postfixes(first, postfixes),
             Self::assign_op_rhs {
-                accesses,
+                postfixes,
+                chained,
                 asop,
                 rhs,
-            } => MarkedAstNode::new(
-                Self::assign_op {
-                    variable: tuplify!(first, name),
-                    accesses,
-                    asop,
-                    value: rhs,
-                },
-                first.mark,
-            ),
+            } => Self::assign_to_postfixes(first, postfixes, chained, asop, rhs),
             bad => panic!("Tried calling from_unit_8() with {bad:?}"),
         }
     }
@@ -796,6 +994,6 @@ impl AstNode {
     /// Program.2: (empty|if_stmt|while_loop|for_loop|continue|break|return_stmt|function_def|function_call|assign_op)* ⟶ block
     /// ```
     pub fn from_program_2(first: MarkedAstNode) -> MarkedAstNode {
-        MarkedAstNode::new(Self::block(tuplify!(first, multiple)), first.mark)
+        Self::statement_block(first)
     }
 }
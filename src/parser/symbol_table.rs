@@ -222,6 +222,7 @@ impl SymbolTable {
             }
             AstNode::assign_op {
                 variable,
+                chained,
                 asop,
                 value,
                 ..
@@ -229,18 +230,9 @@ impl SymbolTable {
                 trace!("Called find_vars_ast() on an assign_op");
                 // Custom `put_local()` implementation because all untrivial asops are read AND write,
                 // so the var must have been evaluated as local ALREADY
-                match vars.get(variable) {
-                    Some(VarClassification::Read) => {
-                        return Err(ParseError::marked(
-                            &format!("local variable '{variable}' referenced before assignment"),
-                            variable.mark.row,
-                            variable.mark.col,
-                        ));
-                    }
-                    Some(VarClassification::Local) => {}
-                    Some(_) => unreachable!(),
-                    None => {
-                        if !matches!(asop.comp, Asop::Assign) {
+                for variable in std::iter::once(variable).chain(chained.iter().map(|(v, _)| v)) {
+                    match vars.get(variable) {
+                        Some(VarClassification::Read) => {
                             return Err(ParseError::marked(
                                 &format!(
                                     "local variable '{variable}' referenced before assignment"
@@ -249,12 +241,42 @@ impl SymbolTable {
                                 variable.mark.col,
                             ));
                         }
-                        vars.insert(variable.clone(), VarClassification::Local);
+                        Some(VarClassification::Local) => {}
+                        Some(_) => unreachable!(),
+                        None => {
+                            if !matches!(asop.comp, Asop::Assign) {
+                                return Err(ParseError::marked(
+                                    &format!(
+                                        "local variable '{variable}' referenced before assignment"
+                                    ),
+                                    variable.mark.row,
+                                    variable.mark.col,
+                                ));
+                            }
+                            vars.insert(variable.clone(), VarClassification::Local);
+                        }
                     }
                 }
 
                 Self::find_vars_op(value, vars, inner_scopes)?;
             }
+            AstNode::subscript_assign_op {
+                container,
+                access,
+                chained,
+                value,
+                ..
+            } => {
+                trace!("Called find_vars_ast() on a subscript_assign_op");
+                Self::find_vars_op(container, vars, inner_scopes)?;
+                Self::find_vars_op(access, vars, inner_scopes)?;
+                // Chains only ever use `=`, so the other targets are plain assignments
+                for (variable, _) in chained {
+                    Self::put_local(variable, vars)?;
+                }
+
+                Self::find_vars_op(value, vars, inner_scopes)?;
+            }
             _ => {
                 // Find vars in all the ast nodes that directly mention them (identity operations)
                 match &node.comp {
@@ -272,6 +294,25 @@ impl SymbolTable {
                             Self::find_vars_op(access, vars, inner_scopes)?;
                         }
                     }
+                    AstNode::call { callee, arguments } => {
+                        trace!("Called find_vars_ast() on a call");
+                        Self::find_vars_op(callee, vars, inner_scopes)?;
+                        for arg in arguments {
+                            Self::find_vars_op(arg, vars, inner_scopes)?;
+                        }
+                    }
+                    AstNode::subscript { value, accesses } => {
+                        trace!("Called find_vars_ast() on a subscript");
+                        Self::find_vars_op(value, vars, inner_scopes)?;
+                        for access in accesses {
+                            Self::find_vars_op(access, vars, inner_scopes)?;
+                        }
+                    }
+                    AstNode::named_expr { target, value } => {
+                        trace!("Called find_vars_ast() on a named_expr");
+                        Self::find_vars_op(value, vars, inner_scopes)?;
+                        Self::put_local(target, vars)?;
+                    }
                     AstNode::list(list) => {
                         trace!("Called find_vars_ast() on a list");
                         for item in list {
@@ -302,6 +343,10 @@ impl SymbolTable {
                         trace!("Called find_vars_ast() on a boolean");
                         // Do nothing
                     }
+                    AstNode::none => {
+                        trace!("Called find_vars_ast() on a none");
+                        // Do nothing
+                    }
                     non_identity_ast!() => {
                         panic!("Tried calling find_vars_ast() with {node:?}");
                     }
@@ -379,10 +424,11 @@ impl SymbolTable {
     pub fn deref_idx(&self, name: &MarkedString) -> Option<usize> {
         if let Some(idx) = self.cell_vars.iter().position(|n| n == name) {
             Some(idx)
-        } else if let Some(idx) = self.free_vars.iter().position(|n| n == name) {
-            Some(idx + self.cell_vars.len())
         } else {
-            None
+            self.free_vars
+                .iter()
+                .position(|n| n == name)
+                .map(|idx| idx + self.cell_vars.len())
         }
     }
 
@@ -5,13 +5,42 @@ use std::fmt::Debug;
 
 use super::markers::*;
 use super::{ParseError, building_blocks::*};
-use crate::{parser::ptag::AstNode, util::TwoWayIterator};
+use crate::parser::ptag::{AstNode, OperationTree};
+use crate::util::TwoWayIterator;
+
+/// How deeply expressions and blocks may nest before parsing gives up, unless the parser is told otherwise. Every level
+/// recurses through Rust in the parser and in each stage after it. On the main thread's 8MB stack in a debug build,
+/// nested `if` blocks are the first to overflow, at 137 levels, so this leaves some room below that.
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 100;
 
 #[derive(Debug, Default, Clone)]
 pub struct Context {
     pub indentation: usize,
     pub in_loop: bool,
     pub in_function: bool,
+    pub depth: usize,
+    pub max_depth: usize,
+}
+
+impl Context {
+    /// The context one nesting level deeper, or an error marked at `next` if that exceeds `max_depth`.
+    fn nested(&self, next: Option<&Token>) -> Result<Self, ParseError> {
+        if self.depth >= self.max_depth {
+            let err = match next {
+                Some(t) => {
+                    let (line, col) = t.line_and_col();
+                    ParseError::marked("maximum nesting depth exceeded", line, col)
+                }
+                None => ParseError::general("maximum nesting depth exceeded"),
+            };
+            // Nothing has been consumed yet at this depth, but every way of going on would be just as deep
+            return Err(err.unrecoverable());
+        }
+
+        let mut context = self.clone();
+        context.depth += 1;
+        Ok(context)
+    }
 }
 
 #[derive(Debug)]
@@ -187,6 +216,25 @@ impl BoolTokenNode {
     }
 }
 
+#[derive(Debug)]
+pub struct NoneTokenNode(usize, usize);
+impl NoneTokenNode {
+    pub fn from_token(token: &Token) -> Self {
+        match token {
+            Token::NONE(line, col) => Self(*line, *col),
+            t => panic!("Attempted to make `NoneTokenNode` from {t:?}"),
+        }
+    }
+
+    pub fn as_ast(&self) -> MarkedAstNode {
+        let mark = Marker {
+            row: self.0,
+            col: self.1,
+        };
+        MarkedAstNode::new(AstNode::none, mark)
+    }
+}
+
 /// The entire script.
 ///
 /// ```
@@ -214,8 +262,8 @@ pub enum ScopedNode {
 /// The contents of a line, including the NEWLINE.
 ///
 /// ```
-/// Unit:  KEYWORD(If) Expr MISC(':') Result
-///      | KEYWORD(While) Expr MISC(':') Result   [l = true]
+/// Unit:  KEYWORD(If) NamedExpr MISC(':') Result
+///      | KEYWORD(While) NamedExpr MISC(':') Result   [l = true]
 ///      | KEYWORD(For) NAME OP(In) Expr MISC(:) Result   [l = true]
 ///  [l] | KEYWORD(Continue) NEWLINE
 ///  [l] | KEYWORD(Break) NEWLINE
@@ -225,8 +273,8 @@ pub enum ScopedNode {
 /// ```
 #[derive(Debug)]
 pub enum UnitNode {
-    If(Box<ExprNode>, Box<ResultNode>),
-    While(Box<ExprNode>, Box<ResultNode>),
+    If(Box<NamedExprNode>, Box<ResultNode>),
+    While(Box<NamedExprNode>, Box<ResultNode>),
     For(NameTokenNode, Box<ExprNode>, Box<ResultNode>),
     Continue,
     Break,
@@ -240,11 +288,17 @@ pub enum UnitNode {
 /// ```
 /// Result: NEWLINE Scoped+   [n += 1]
 ///       | NAME SideEffect NEWLINE
+///   [l] | KEYWORD(Continue) NEWLINE
+///   [l] | KEYWORD(Break) NEWLINE
+///   [f] | KEYWORD(Return) Expr? NEWLINE
 /// ```
 #[derive(Debug)]
 pub enum ResultNode {
     MultiLine(Plus<ScopedNode>),
     InLine(NameTokenNode, Box<SideEffectNode>),
+    Continue,
+    Break,
+    Return(Maybe<ExprNode>),
 }
 
 /// A helper node to give function bodies the option to be a single in-line return statement.
@@ -262,14 +316,28 @@ pub enum BodyNode {
 /// To call NAME as a function, or assign to it a value as a variable or indexed object.
 ///
 /// ```
-/// SideEffect: BRACKET('(') List? BRACKET(')')
-///           | Index* ASOP Expr
+/// SideEffect: Postfix* Assign?
 /// ```
+/// Without an `Assign` the chain must end in a call, and with one it must be made up of indexes only.
 #[derive(Debug)]
-pub enum SideEffectNode {
-    Call(Maybe<ListNode>),
-    Asop(Star<IndexNode>, AsopTokenNode, Box<ExprNode>),
-}
+pub struct SideEffectNode(Star<PostfixNode>, Maybe<AssignNode>);
+
+/// Helper node for SideEffect to assign a value.
+///
+/// ```
+/// Assign: ASOP Expr ChainedAssign*
+/// ```
+/// With any `ChainedAssign`, every `Expr` but the last is another target and every ASOP must be `=`.
+#[derive(Debug)]
+pub struct AssignNode(AsopTokenNode, Box<ExprNode>, Star<ChainedAssignNode>);
+
+/// Helper node for Assign to assign the same value to several targets, as in `a = b = 0`.
+///
+/// ```
+/// ChainedAssign: ASOP Expr
+/// ```
+#[derive(Debug)]
+pub struct ChainedAssignNode(AsopTokenNode, Box<ExprNode>);
 
 /// Any expression that can return a value.
 ///
@@ -279,6 +347,18 @@ pub enum SideEffectNode {
 #[derive(Debug)]
 pub struct ExprNode(Box<ExprUnaryNode>, Star<ExprBinaryNode>);
 
+/// An expression that may also assign its value to NAME.
+///
+/// ```
+/// NamedExpr: NAME ASOP(Walrus) Expr
+///          | Expr
+/// ```
+#[derive(Debug)]
+pub enum NamedExprNode {
+    Named(NameTokenNode, Box<ExprNode>),
+    Expr(Box<ExprNode>),
+}
+
 /// An expression potentially starting with a unary operation.
 ///
 /// ```
@@ -297,22 +377,24 @@ pub enum ExprUnaryNode {
 ///
 /// ```
 /// ExprUnit: NAME NameExpr
-///         | BRACKET('(') Expr BRACKET(')')
+///         | BRACKET('(') NamedExpr BRACKET(')')
 ///         | BRACKET('[') List? BRACKET(']')
 ///         | BRACKET('{') BracExpr? BRACKET('}')
 ///         | STRING
 ///         | NUMBER
 ///         | BOOLEAN
+///         | NONE
 /// ```
 #[derive(Debug)]
 pub enum ExprUnitNode {
     Name(NameTokenNode, Box<NameExprNode>),
-    Paren(Box<ExprNode>),
+    Paren(Box<NamedExprNode>),
     Bracket(Maybe<ListNode>),
     Brace(Maybe<BracExprNode>),
     String(StringTokenNode),
     Number(NumberTokenNode),
     Bool(BoolTokenNode),
+    None(NoneTokenNode),
 }
 
 /// Helper node for Expr to have multiple subexpressions joined through binary operations.
@@ -326,13 +408,21 @@ pub struct ExprBinaryNode(OpTokenNode, Box<ExprUnitNode>);
 /// Helper node for ExprUnit to access a NAME in ways outside of basic value-retrieval.
 ///
 /// ```
-/// NameExpr: BRACKET('(') List? BRACKET(')')
-///         | Index*
+/// NameExpr: Postfix*
+/// ```
+#[derive(Debug)]
+pub struct NameExprNode(Star<PostfixNode>);
+
+/// A call or index applied to the value before it.
+///
+/// ```
+/// Postfix: BRACKET('(') List? BRACKET(')')
+///        | Index
 /// ```
 #[derive(Debug)]
-pub enum NameExprNode {
+pub enum PostfixNode {
     Call(Maybe<ListNode>),
-    Index(Star<IndexNode>),
+    Index(Box<IndexNode>),
 }
 
 /// Helper node for ExprUnit to create sets and dictionaries.
@@ -395,7 +485,7 @@ pub struct DictNode(StringTokenNode, Box<ExprNode>, Star<DictTailNode>);
 #[derive(Debug)]
 pub struct DictTailNode(StringTokenNode, Box<ExprNode>);
 
-/// The index of an indexable NAME.
+/// The index of an indexable value.
 ///
 /// ```
 /// Index: BRACKET('[') Expr BRACKET(']')
@@ -452,8 +542,7 @@ macro_rules! match_token {
                     stringify!($token_node),
                     $err_message
                 );
-                let (line, col) = t.line_and_col();
-                return ($advanced, Err(ParseError::marked($err_message, line, col)));
+                return ($advanced, Err(unexpected_token(t, $err_message)));
             }
             None => {
                 error!(
@@ -476,8 +565,7 @@ macro_rules! match_token {
             Some($token_pat) => {}
             Some(t) => {
                 trace!("{} ({t:?} != {})", $err_message, stringify!($token_pat));
-                let (line, col) = t.line_and_col();
-                return ($advanced, Err(ParseError::marked($err_message, line, col)));
+                return ($advanced, Err(unexpected_token(t, $err_message)));
             }
             None => {
                 error!("The token stream somehow ended early");
@@ -492,6 +580,28 @@ macro_rules! match_token {
     }};
 }
 
+/// The error for `token` being where the grammar expected something else, described by `msg`. Characters that the
+/// grammar has no use for anywhere get named instead, since no other position would have fixed them.
+fn unexpected_token(token: &Token, msg: &str) -> ParseError {
+    let (line, col) = token.line_and_col();
+    match token {
+        Token::MISC(c, _, _) if !matches!(c, ',' | ':') => {
+            ParseError::marked(&format!("unexpected character '{c}'"), line, col)
+        }
+        _ => ParseError::marked(msg, line, col),
+    }
+}
+
+/// The error for a `continue`, `break` or `return` where the context doesn't allow one, worded like CPython's.
+fn misplaced_jump(keyword: &Keyword, line: usize, col: usize) -> ParseError {
+    let (msg, width) = match keyword {
+        Keyword::Continue => ("`continue` not properly in loop", 8),
+        Keyword::Break => ("`break` outside loop", 5),
+        _ => ("`return` outside function", 6),
+    };
+    ParseError::marked(msg, line, col).spanning(width)
+}
+
 pub struct ParseTokensRes<N: ParseTreeNode> {
     pub parse_node: N,
     pub ast_node: MarkedAstNode,
@@ -506,9 +616,15 @@ impl<N: ParseTreeNode> ParseTokensRes<N> {
     }
 }
 
-pub fn parse_tokens(token_stream: &Vec<Token>) -> Result<ParseTokensRes<ProgramNode>, ParseError> {
+pub fn parse_tokens(
+    token_stream: &Vec<Token>,
+    max_depth: usize,
+) -> Result<ParseTokensRes<ProgramNode>, ParseError> {
     debug!("parse_tokens() started");
-    let context = Context::default();
+    let context = Context {
+        max_depth,
+        ..Default::default()
+    };
     let mut iter = TwoWayIterator::from_source(token_stream);
     ProgramNode::parse(&mut iter, &context).1
 }
@@ -538,8 +654,8 @@ impl<N: ParseTreeNode> ParseTreeNode for Star<N> {
                     ast_group.push(n.ast_node);
                 }
                 Err(e) => {
-                    // Ignore the actual error if the next token was not matched
-                    if result.0 == 1 {
+                    // Ignore the actual error if the next token was not matched, unless it can't be recovered from
+                    if result.0 == 1 && e.recoverable {
                         token_stream.rev();
                     // Propagate the error if the next token WAS matched
                     } else {
@@ -601,8 +717,8 @@ impl<N: ParseTreeNode> ParseTreeNode for Plus<N> {
                     ast_group.push(n.ast_node);
                 }
                 Err(e) => {
-                    // Ignore the actual error if the next token was not matched
-                    if result.0 == 1 {
+                    // Ignore the actual error if the next token was not matched, unless it can't be recovered from
+                    if result.0 == 1 && e.recoverable {
                         token_stream.rev();
                         // Unless no nodes have been matched, then propagate the error anyway
                         if parse_group.is_empty() {
@@ -667,8 +783,8 @@ impl<N: ParseTreeNode> ParseTreeNode for Maybe<N> {
                 )
             }
             Err(e) => {
-                // Ignore the actual error if the next token was not matched
-                if result.0 == 1 {
+                // Ignore the actual error if the next token was not matched, unless it can't be recovered from
+                if result.0 == 1 && e.recoverable {
                     token_stream.rev();
                 // Propagate the error if the next token WAS matched
                 } else {
@@ -831,15 +947,12 @@ impl ParseTreeNode for ScopedNode {
             // IMPORTANT: If any new tokens are added here, audit to see if they should be added to the "unexpected token"
             // error message in `ProgramNode::parse()`.
             _ => {
-                let (line, col) = first.line_and_col();
-
                 trace!("[ScopedNode::parse()] Unexpected token {first:?}");
                 (
                     advanced,
-                    Err(ParseError::marked(
+                    Err(unexpected_token(
+                        first,
                         "unexpected token, expected: newline, indentation",
-                        line,
-                        col,
                     )),
                 )
             }
@@ -847,6 +960,16 @@ impl ParseTreeNode for ScopedNode {
     }
 }
 
+/// The spelling of a literal that looks like a name but can never be assigned to.
+fn reserved_literal(token: &Token) -> Option<&'static str> {
+    match token {
+        Token::BOOL(true, _, _) => Some("True"),
+        Token::BOOL(false, _, _) => Some("False"),
+        Token::NONE(_, _) => Some("None"),
+        _ => None,
+    }
+}
+
 impl ParseTreeNode for UnitNode {
     fn parse<'a>(
         token_stream: &mut TwoWayIterator<Token>,
@@ -870,8 +993,8 @@ impl ParseTreeNode for UnitNode {
             Token::KEYWORD(Keyword::If, _, _) => {
                 trace!("[UnitNode::parse()] Started KEYWORD(If) arm");
 
-                /* `Expr` */
-                let expr = match_node!(ExprNode, token_stream, context, advanced);
+                /* `NamedExpr` */
+                let expr = match_node!(NamedExprNode, token_stream, context, advanced);
 
                 /* `MISC(':')` */
                 match_token!(
@@ -899,8 +1022,8 @@ impl ParseTreeNode for UnitNode {
                 context.in_loop = true;
                 let context = &context;
 
-                /* `Expr` */
-                let expr = match_node!(ExprNode, token_stream, context, advanced);
+                /* `NamedExpr` */
+                let expr = match_node!(NamedExprNode, token_stream, context, advanced);
 
                 /* `MISC(':')` */
                 match_token!(
@@ -928,6 +1051,17 @@ impl ParseTreeNode for UnitNode {
                 context.in_loop = true;
                 let context = &context;
 
+                if let Some(literal) = token_stream.peek().and_then(reserved_literal) {
+                    let (line, col) = token_stream.peek().unwrap().line_and_col();
+                    return (
+                        advanced,
+                        Err(
+                            ParseError::marked(&format!("cannot assign to {literal}"), line, col)
+                                .unrecoverable(),
+                        ),
+                    );
+                }
+
                 /* `NAME` */
                 let name = match_token!(
                     Token::NAME(_, _, _),
@@ -1039,8 +1173,10 @@ impl ParseTreeNode for UnitNode {
             Token::KEYWORD(Keyword::Def, _, _) => {
                 trace!("[UnitNode::parse()] Started KEYWORD(Def) arm");
 
+                // A loop around the definition doesn't surround the function's body
                 let mut context = context.clone();
                 context.in_function = true;
+                context.in_loop = false;
                 let context = &context;
 
                 /* `NAME` */
@@ -1118,16 +1254,34 @@ impl ParseTreeNode for UnitNode {
                     )),
                 )
             }
+            Token::BOOL(_, line, col) | Token::NONE(line, col)
+                if matches!(token_stream.peek(), Some(Token::ASOP(_, _, _))) =>
+            {
+                trace!("[UnitNode::parse()] Assignment to reserved literal {first:?}");
+                (
+                    advanced,
+                    Err(ParseError::marked(
+                        &format!(
+                            "cannot assign to {}",
+                            reserved_literal(first).expect("Token should be a literal")
+                        ),
+                        *line,
+                        *col,
+                    )),
+                )
+            }
+            Token::KEYWORD(
+                kw @ (Keyword::Continue | Keyword::Break | Keyword::Return),
+                row,
+                col,
+            ) => (advanced, Err(misplaced_jump(kw, *row, *col))),
             _ => {
-                let (line, col) = first.line_and_col();
-
                 trace!("[UnitNode::parse()] Unexpected token {first:?}");
                 (
                     advanced,
-                    Err(ParseError::marked(
+                    Err(unexpected_token(
+                        first,
                         "unexpected token, expected: `if`, `while`, `for`, `continue`, `break`, `def`, name",
-                        line,
-                        col,
                     )),
                 )
             }
@@ -1159,7 +1313,10 @@ impl ParseTreeNode for ResultNode {
             Token::NEWLINE(_, _) => {
                 trace!("[ResultNode::parse()] Started NEWLINE arm");
 
-                let mut context = context.clone();
+                let mut context = match context.nested(token_stream.peek()) {
+                    Ok(context) => context,
+                    Err(e) => return (advanced, Err(e)),
+                };
                 context.indentation += 1;
                 let context = &context;
 
@@ -1193,18 +1350,86 @@ impl ParseTreeNode for ResultNode {
                     )),
                 )
             }
-            _ => {
-                let (line, col) = first.line_and_col();
+            Token::KEYWORD(Keyword::Continue, row, col) if context.in_loop => {
+                trace!("[ResultNode::parse()] Started KEYWORD(Continue) arm");
 
+                /* `NEWLINE` */
+                match_token!(
+                    Token::NEWLINE(_, _),
+                    "expected a newline",
+                    token_stream,
+                    advanced
+                );
+
+                let mark = Marker {
+                    row: *row,
+                    col: *col,
+                };
                 (
                     advanced,
-                    Err(ParseError::marked(
-                        "unexpected token, expected: newline, name",
-                        line,
-                        col,
+                    Ok(ParseTokensRes::new(
+                        Self::Continue,
+                        AstNode::from_result_3(MarkedAstNode::new(AstNode::r#continue, mark)),
+                    )),
+                )
+            }
+            Token::KEYWORD(Keyword::Break, row, col) if context.in_loop => {
+                trace!("[ResultNode::parse()] Started KEYWORD(Break) arm");
+
+                /* `NEWLINE` */
+                match_token!(
+                    Token::NEWLINE(_, _),
+                    "expected a newline",
+                    token_stream,
+                    advanced
+                );
+
+                let mark = Marker {
+                    row: *row,
+                    col: *col,
+                };
+                (
+                    advanced,
+                    Ok(ParseTokensRes::new(
+                        Self::Break,
+                        AstNode::from_result_4(MarkedAstNode::new(AstNode::r#break, mark)),
                     )),
                 )
             }
+            Token::KEYWORD(Keyword::Return, _, _) if context.in_function => {
+                trace!("[ResultNode::parse()] Started KEYWORD(Return) arm");
+
+                /* `Expr?` */
+                let expr_maybe = match_meta_node!(ExprNode, Maybe, token_stream, context, advanced);
+
+                /* `NEWLINE` */
+                match_token!(
+                    Token::NEWLINE(_, _),
+                    "expected a newline",
+                    token_stream,
+                    advanced
+                );
+
+                (
+                    advanced,
+                    Ok(ParseTokensRes::new(
+                        Self::Return(expr_maybe.parse_node),
+                        AstNode::from_result_5(expr_maybe.ast_node),
+                    )),
+                )
+            }
+            Token::KEYWORD(
+                kw @ (Keyword::Continue | Keyword::Break | Keyword::Return),
+                row,
+                col,
+            ) => (advanced, Err(misplaced_jump(kw, *row, *col))),
+            _ => (
+                advanced,
+                Err(unexpected_token(
+                    first,
+                    "unexpected token, expected: newline, name, `continue`, `break`, `return`",
+                )),
+            ),
         }
     }
 }
@@ -1233,7 +1458,10 @@ impl ParseTreeNode for BodyNode {
             Token::NEWLINE(_, _) => {
                 trace!("[BodyNode::parse()] Started NEWLINE arm");
 
-                let mut context = context.clone();
+                let mut context = match context.nested(token_stream.peek()) {
+                    Ok(context) => context,
+                    Err(e) => return (advanced, Err(e)),
+                };
                 context.indentation += 1;
                 let context = &context;
 
@@ -1271,18 +1499,13 @@ impl ParseTreeNode for BodyNode {
                     )),
                 )
             }
-            _ => {
-                let (line, col) = first.line_and_col();
-
-                (
-                    advanced,
-                    Err(ParseError::marked(
-                        "unexpected token, expected: newline, `return`",
-                        line,
-                        col,
-                    )),
-                )
-            }
+            _ => (
+                advanced,
+                Err(unexpected_token(
+                    first,
+                    "unexpected token, expected: newline, `return`",
+                )),
+            ),
         }
     }
 }
@@ -1294,73 +1517,166 @@ impl ParseTreeNode for SideEffectNode {
     ) -> (usize, Result<ParseTokensRes<Self>, ParseError>) {
         debug!("SideEffectNode::parse() started");
 
-        let first = if let Some(token) = token_stream.next() {
-            token
-        } else {
+        let mut advanced = 0;
+
+        /* `Postfix*` */
+        let postfix_star = match_meta_node!(PostfixNode, Star, token_stream, context, advanced);
+
+        /* `Assign?` */
+        let assign_maybe = match_meta_node!(AssignNode, Maybe, token_stream, context, advanced);
+
+        if let Some(assign) = &assign_maybe.parse_node.0 {
+            if let Asop::Walrus = (assign.0).0 {
+                return (
+                    advanced,
+                    Err(ParseError::marked(
+                        "`:=` is only allowed inside an expression, use `=` instead",
+                        (assign.0).1,
+                        (assign.0).2,
+                    )
+                    .spanning(Asop::Walrus.symbol().len())),
+                );
+            }
+            // Calls earlier in the chain are fine, as in `f()[0] = 1`
+            if let Some(PostfixNode::Call(_)) = postfix_star.parse_node.0.last() {
+                return (
+                    advanced,
+                    Err(ParseError::marked(
+                        "cannot assign to a function call",
+                        (assign.0).1,
+                        (assign.0).2,
+                    )
+                    .spanning((assign.0).0.symbol().len())),
+                );
+            }
+        } else if !matches!(postfix_star.parse_node.0.last(), Some(PostfixNode::Call(_))) {
+            let msg = "expected an assignment operator";
             return (
-                1,
-                Err(ParseError::general(
-                    "Grammar error: the token stream somehow ended early...",
-                )),
+                advanced,
+                Err(token_stream
+                    .peek()
+                    .map(|t| unexpected_token(t, msg))
+                    .unwrap_or_else(|| ParseError::marked(msg, 0, 0))
+                    .unrecoverable()),
             );
-        };
+        }
 
-        let mut advanced = 1;
+        (
+            advanced,
+            Ok(ParseTokensRes::new(
+                Self(postfix_star.parse_node, assign_maybe.parse_node),
+                AstNode::from_side_effect(postfix_star.ast_node, assign_maybe.ast_node),
+            )),
+        )
+    }
+}
 
-        match first {
-            Token::BRACKET('(', _, _) => {
-                trace!("[SideEffectNode::parse()] Started BRACKET('(') arm");
+impl ParseTreeNode for AssignNode {
+    fn parse<'a>(
+        token_stream: &mut TwoWayIterator<Token>,
+        context: &Context,
+    ) -> (usize, Result<ParseTokensRes<Self>, ParseError>) {
+        debug!("AssignNode::parse() started");
 
-                /* `List?` */
-                let list_maybe = match_meta_node!(ListNode, Maybe, token_stream, context, advanced);
+        let mut advanced = 0;
 
-                /* `BRACKET(')')` */
-                match_token!(
-                    Token::BRACKET(')', _, _),
-                    "expected a `)`",
-                    token_stream,
-                    advanced
+        /* `ASOP` */
+        let asop = match_token!(
+            Token::ASOP(_, _, _),
+            AsopTokenNode,
+            "expected an assignment operator",
+            token_stream,
+            advanced
+        );
+        let asop_ast = asop.as_ast();
+
+        /* `Expr` */
+        let expr = match_node!(ExprNode, token_stream, context, advanced);
+
+        /* `ChainedAssign*` */
+        let chained_star =
+            match_meta_node!(ChainedAssignNode, Star, token_stream, context, advanced);
+
+        if let AstNode::multiple(chained) = &chained_star.ast_node.comp
+            && !chained.is_empty()
+        {
+            let mut asops =
+                std::iter::once(&asop).chain(chained_star.parse_node.0.iter().map(|c| &c.0));
+            if let Some(bad_asop) = asops.find(|a| !matches!(a.0, Asop::Assign)) {
+                return (
+                    advanced,
+                    Err(ParseError::marked(
+                        "only `=` can be used in a chained assignment",
+                        bad_asop.1,
+                        bad_asop.2,
+                    )
+                    .spanning(bad_asop.0.symbol().len())),
                 );
+            }
 
-                (
+            // Every `Expr` but the last is another target, so it has to turn out to be assignable after all
+            let targets = std::iter::once(&expr.ast_node).chain(&chained[..chained.len() - 1]);
+            for target in targets {
+                let msg = match &target.comp {
+                    AstNode::expr(op_tree) => match &op_tree.comp {
+                        OperationTree::Identity(MarkedAstNode {
+                            comp: AstNode::variable { .. },
+                            ..
+                        }) => continue,
+                        OperationTree::Identity(MarkedAstNode {
+                            comp: AstNode::function_call { .. } | AstNode::call { .. },
+                            ..
+                        }) => "cannot assign to a function call",
+                        _ => "cannot assign to an expression",
+                    },
+                    _ => "cannot assign to an expression",
+                };
+                return (
                     advanced,
-                    Ok(ParseTokensRes::new(
-                        Self::Call(list_maybe.parse_node),
-                        AstNode::from_side_effect_1(list_maybe.ast_node),
-                    )),
-                )
+                    Err(ParseError::marked(msg, target.mark.row, target.mark.col)),
+                );
             }
-            _ => {
-                trace!("[SideEffectNode::parse()] Started Index* arm");
+        }
 
-                advanced -= 1;
-                token_stream.rev();
+        (
+            advanced,
+            Ok(ParseTokensRes::new(
+                Self(asop, Box::new(expr.parse_node), chained_star.parse_node),
+                AstNode::from_assign(asop_ast, expr.ast_node, chained_star.ast_node),
+            )),
+        )
+    }
+}
 
-                /* `Index*` */
-                let index_star = match_meta_node!(IndexNode, Star, token_stream, context, advanced);
+impl ParseTreeNode for ChainedAssignNode {
+    fn parse<'a>(
+        token_stream: &mut TwoWayIterator<Token>,
+        context: &Context,
+    ) -> (usize, Result<ParseTokensRes<Self>, ParseError>) {
+        debug!("ChainedAssignNode::parse() started");
 
-                /* `ASOP` */
-                let asop = match_token!(
-                    Token::ASOP(_, _, _),
-                    AsopTokenNode,
-                    "expected an assignment operator",
-                    token_stream,
-                    advanced
-                );
-                let asop_ast = asop.as_ast();
+        let mut advanced = 0;
 
-                /* `Expr` */
-                let expr = match_node!(ExprNode, token_stream, context, advanced);
+        /* `ASOP` */
+        let asop = match_token!(
+            Token::ASOP(_, _, _),
+            AsopTokenNode,
+            "expected an assignment operator",
+            token_stream,
+            advanced
+        );
+        let asop_ast = asop.as_ast();
 
-                (
-                    advanced,
-                    Ok(ParseTokensRes::new(
-                        Self::Asop(index_star.parse_node, asop, Box::new(expr.parse_node)),
-                        AstNode::from_side_effect_2(index_star.ast_node, asop_ast, expr.ast_node),
-                    )),
-                )
-            }
-        }
+        /* `Expr` */
+        let expr = match_node!(ExprNode, token_stream, context, advanced);
+
+        (
+            advanced,
+            Ok(ParseTokensRes::new(
+                Self(asop, Box::new(expr.parse_node)),
+                AstNode::from_chained_assign(asop_ast, expr.ast_node),
+            )),
+        )
     }
 }
 
@@ -1373,6 +1689,11 @@ impl ParseTreeNode for ExprNode {
 
         let mut advanced = 0;
 
+        let context = &match context.nested(token_stream.peek()) {
+            Ok(context) => context,
+            Err(e) => return (advanced, Err(e)),
+        };
+
         /* `ExprUnary` */
         let expr_unary = match_node!(ExprUnaryNode, token_stream, context, advanced);
 
@@ -1390,6 +1711,79 @@ impl ParseTreeNode for ExprNode {
     }
 }
 
+impl ParseTreeNode for NamedExprNode {
+    fn parse<'a>(
+        token_stream: &mut TwoWayIterator<Token>,
+        context: &Context,
+    ) -> (usize, Result<ParseTokensRes<Self>, ParseError>) {
+        debug!("NamedExprNode::parse() started");
+
+        let mut advanced = 0;
+
+        // Only a NAME directly followed by `:=` is a named expression, anything else is a plain Expr
+        let is_named = matches!(token_stream.next(), Some(Token::NAME(_, _, _)))
+            && matches!(token_stream.peek(), Some(Token::ASOP(Asop::Walrus, _, _)));
+        token_stream.rev();
+
+        if is_named {
+            trace!("[NamedExprNode::parse()] Started NAME arm");
+
+            /* `NAME` */
+            let name = match_token!(
+                Token::NAME(_, _, _),
+                NameTokenNode,
+                "expected a name",
+                token_stream,
+                advanced
+            );
+            let name_ast = name.as_ast();
+
+            /* `ASOP(Walrus)` */
+            match_token!(
+                Token::ASOP(Asop::Walrus, _, _),
+                "expected `:=`",
+                token_stream,
+                advanced
+            );
+
+            /* `Expr` */
+            let expr = match_node!(ExprNode, token_stream, context, advanced);
+
+            return (
+                advanced,
+                Ok(ParseTokensRes::new(
+                    Self::Named(name, Box::new(expr.parse_node)),
+                    AstNode::from_named_expr_1(name_ast, expr.ast_node),
+                )),
+            );
+        }
+
+        trace!("[NamedExprNode::parse()] Started Expr arm");
+
+        /* `Expr` */
+        let expr = match_node!(ExprNode, token_stream, context, advanced);
+
+        // Catch the classic `if x = 1:` mistake, since a plain `=` can never follow a condition
+        if let Some(Token::ASOP(Asop::Assign, line, col)) = token_stream.peek() {
+            return (
+                advanced,
+                Err(
+                    ParseError::marked("invalid syntax; did you mean `==`?", *line, *col)
+                        .unrecoverable(),
+                ),
+            );
+        }
+
+        (
+            advanced,
+            Ok(ParseTokensRes::new(
+                Self::Expr(Box::new(expr.parse_node)),
+                AstNode::from_named_expr_2(expr.ast_node),
+            )),
+        )
+    }
+}
+
 impl ParseTreeNode for ExprUnaryNode {
     fn parse<'a>(
         token_stream: &mut TwoWayIterator<Token>,
@@ -1502,8 +1896,8 @@ impl ParseTreeNode for ExprUnitNode {
             Token::BRACKET('(', _, _) => {
                 trace!("[ExprUnitNode::parse()] Started BRACKET('(') arm");
 
-                /* `Expr` */
-                let expr = match_node!(ExprNode, token_stream, context, advanced);
+                /* `NamedExpr` */
+                let expr = match_node!(NamedExprNode, token_stream, context, advanced);
 
                 /* `BRACKET(')')` */
                 match_token!(
@@ -1608,16 +2002,42 @@ impl ParseTreeNode for ExprUnitNode {
                     )),
                 )
             }
-            _ => {
-                trace!("[ExprUnitNode::parse()] Unexpected token {first:?}");
-                let (line, col) = first.line_and_col();
+            Token::NONE(_, _) => {
+                trace!("[ExprUnitNode::parse()] Started NONE arm");
+
+                let n = NoneTokenNode::from_token(first);
+                let n_ast = n.as_ast();
+
+                (
+                    advanced,
+                    Ok(ParseTokensRes::new(
+                        Self::None(n),
+                        AstNode::from_expr_unit_8(n_ast),
+                    )),
+                )
+            }
+            Token::KEYWORD(Keyword::Def, row, col) => {
+                trace!("[ExprUnitNode::parse()] Started KEYWORD(Def) arm");
 
+                // Unrecoverable, so that a quantifier doesn't swallow this for a vaguer error
                 (
                     advanced,
                     Err(ParseError::marked(
-                        "unexpected token, expected: name, `(`, `[`, `{`, string, number, boolean",
-                        line,
-                        col,
+                        "expected an expression, but `def` is a statement: define the function on its own line and use its name instead (there are no lambdas)",
+                        *row,
+                        *col,
+                    )
+                    .spanning(3)
+                    .unrecoverable()),
+                )
+            }
+            _ => {
+                trace!("[ExprUnitNode::parse()] Unexpected token {first:?}");
+                (
+                    advanced,
+                    Err(unexpected_token(
+                        first,
+                        "expected an expression: a name, number, string, `(`, `[`, `{`, boolean, or `None`",
                     )),
                 )
             }
@@ -1642,20 +2062,22 @@ impl ParseTreeNode for ExprBinaryNode {
             token_stream,
             advanced
         );
+        let op_ast = op.as_ast();
+
+        /* `ExprUnit` */
+        let expr_unit = match_node!(ExprUnitNode, token_stream, context, advanced);
+
+        // Only checked once the operand is matched too, since `Star` ignores errors from a node that failed right after
+        // its first token
         if let Op::Not | Op::BWNot = op.0 {
             return (
                 advanced,
-                Err(ParseError::marked(
-                    "unary operator not allowed here",
-                    op.1,
-                    op.2,
-                )),
+                Err(
+                    ParseError::marked("unary operator not allowed here", op.1, op.2)
+                        .spanning(op.0.symbol().len()),
+                ),
             );
         }
-        let op_ast = op.as_ast();
-
-        /* `ExprUnit` */
-        let expr_unit = match_node!(ExprUnitNode, token_stream, context, advanced);
 
         (
             advanced,
@@ -1674,6 +2096,28 @@ impl ParseTreeNode for NameExprNode {
     ) -> (usize, Result<ParseTokensRes<Self>, ParseError>) {
         debug!("NameExprNode::parse() started");
 
+        let mut advanced = 0;
+
+        /* `Postfix*` */
+        let postfix_star = match_meta_node!(PostfixNode, Star, token_stream, context, advanced);
+
+        (
+            advanced,
+            Ok(ParseTokensRes::new(
+                Self(postfix_star.parse_node),
+                AstNode::from_name_expr(postfix_star.ast_node),
+            )),
+        )
+    }
+}
+
+impl ParseTreeNode for PostfixNode {
+    fn parse<'a>(
+        token_stream: &mut TwoWayIterator<Token>,
+        context: &Context,
+    ) -> (usize, Result<ParseTokensRes<Self>, ParseError>) {
+        debug!("PostfixNode::parse() started");
+
         let first = if let Some(token) = token_stream.next() {
             token
         } else {
@@ -1689,7 +2133,7 @@ impl ParseTreeNode for NameExprNode {
 
         match first {
             Token::BRACKET('(', _, _) => {
-                trace!("[NameExprNode::parse()] Started BRACKET('(') arm");
+                trace!("[PostfixNode::parse()] Started BRACKET('(') arm");
 
                 /* `List?` */
                 let list_maybe = match_meta_node!(ListNode, Maybe, token_stream, context, advanced);
@@ -1706,24 +2150,24 @@ impl ParseTreeNode for NameExprNode {
                     advanced,
                     Ok(ParseTokensRes::new(
                         Self::Call(list_maybe.parse_node),
-                        AstNode::from_name_expr_1(list_maybe.ast_node),
+                        AstNode::from_postfix_1(list_maybe.ast_node),
                     )),
                 )
             }
             _ => {
-                trace!("[NameExprNode::parse()] Started Index* arm");
+                trace!("[PostfixNode::parse()] Started Index arm");
 
                 advanced -= 1;
                 token_stream.rev();
 
-                /* `Index*` */
-                let index_star = match_meta_node!(IndexNode, Star, token_stream, context, advanced);
+                /* `Index` */
+                let index = match_node!(IndexNode, token_stream, context, advanced);
 
                 (
                     advanced,
                     Ok(ParseTokensRes::new(
-                        Self::Index(index_star.parse_node),
-                        AstNode::from_name_expr_2(index_star.ast_node),
+                        Self::Index(Box::new(index.parse_node)),
+                        AstNode::from_postfix_2(index.ast_node),
                     )),
                 )
             }
@@ -2028,3 +2472,316 @@ impl ParseTreeNode for IndexNode {
 }
 
 /* TPG ENDS HERE */
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::parser::ptag::AstNode;
+    use crate::util::with_main_thread_stack;
+
+    #[test]
+    fn test_expr_unit_expected_expression() {
+        let err = Parser::new().parse_from_str("x = * 3\n").err().unwrap();
+        assert_eq!(
+            err.msg,
+            "expected an expression: a name, number, string, `(`, `[`, `{`, boolean, or `None`"
+        );
+        assert!(err.to_string().contains("(unset:1:5)"));
+
+        let err = Parser::new().parse_from_str("y = [1, ==]\n").err().unwrap();
+        assert_eq!(
+            err.msg,
+            "expected an expression: a name, number, string, `(`, `[`, `{`, boolean, or `None`"
+        );
+        assert!(err.to_string().contains("(unset:1:9)"));
+    }
+
+    #[test]
+    fn test_walrus_only_in_expressions() {
+        assert!(Parser::new().parse_from_str("x = (y := 2) + 1\n").is_ok());
+        assert!(
+            Parser::new()
+                .parse_from_str("if y := 2:\n    x = y\n")
+                .is_ok()
+        );
+
+        let err = Parser::new().parse_from_str("x := 3\n").err().unwrap();
+        assert_eq!(
+            err.msg,
+            "`:=` is only allowed inside an expression, use `=` instead"
+        );
+        assert!(err.to_string().contains("(unset:1:3)"));
+    }
+
+    #[test]
+    fn test_assign_in_condition() {
+        for (source, position) in [
+            ("if x = 1:\n    y = 2\n", "(unset:1:6)"),
+            ("while x = 1:\n    y = 2\n", "(unset:1:9)"),
+            ("y = (x = 1)\n", "(unset:1:8)"),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            assert_eq!(err.msg, "invalid syntax; did you mean `==`?");
+            assert!(err.to_string().contains(position), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_postfix_chains() {
+        for source in [
+            "x = m[0][1]\n",
+            "x = f()[0]\n",
+            "callbacks[0]()\n",
+            "f()()\n",
+            "m[0][1] = 2\n",
+            "f()[0] = 1\n",
+            "callbacks[0]()[1] += 2\n",
+            "f()[0][1] = b = 3\n",
+        ] {
+            assert!(Parser::new().parse_from_str(source).is_ok(), "{source}");
+        }
+
+        for (source, msg, position) in [
+            (
+                "f() = 1\n",
+                "cannot assign to a function call",
+                "(unset:1:5)",
+            ),
+            (
+                "f()[0]() = 1\n",
+                "cannot assign to a function call",
+                "(unset:1:10)",
+            ),
+            ("m[0]\n", "expected an assignment operator", "(unset:1:5)"),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            assert_eq!(err.msg, msg);
+            assert!(err.to_string().contains(position), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_maximum_nesting_depth() {
+        // Each level goes a little deeper into the stack than a 2MB test thread allows
+        with_main_thread_stack(|| {
+            let nested =
+                |depth: usize| format!("x = {}1{}\n", "(".repeat(depth), ")".repeat(depth));
+
+            assert!(Parser::new().parse_from_str(&nested(99)).is_ok());
+            let blocks = (0..99)
+                .map(|depth| format!("{}if True:\n", "    ".repeat(depth)))
+                .collect::<String>();
+            assert!(
+                Parser::new()
+                    .parse_from_str(&format!("{blocks}{}x = 1\n", "    ".repeat(99)))
+                    .is_ok()
+            );
+
+            let err = Parser::new().parse_from_str(&nested(150)).err().unwrap();
+            assert_eq!(err.msg, "maximum nesting depth exceeded");
+            assert!(err.to_string().contains("(unset:1:105)"));
+
+            // Every kind of bracket reports the limit rather than the bracket it would have expected next
+            for (open, close, position) in [
+                ("[", "]", "(unset:1:105)"),
+                ("{\"a\": ", "}", "(unset:1:605)"),
+                ("abs(", ")", "(unset:1:405)"),
+            ] {
+                let source = format!("x = {}1{}\n", open.repeat(150), close.repeat(150));
+                let err = Parser::new().parse_from_str(&source).err().unwrap();
+                assert_eq!(err.msg, "maximum nesting depth exceeded");
+                assert!(err.to_string().contains(position), "{err}");
+            }
+
+            // The limit can be changed either way
+            let err = Parser::new()
+                .with_max_nesting_depth(10)
+                .parse_from_str(&nested(11))
+                .err()
+                .unwrap();
+            assert_eq!(err.msg, "maximum nesting depth exceeded");
+            assert!(
+                Parser::new()
+                    .with_max_nesting_depth(120)
+                    .parse_from_str(&nested(119))
+                    .is_ok()
+            );
+        });
+    }
+
+    #[test]
+    fn test_assign_to_reserved_literal() {
+        for (source, literal, col) in [
+            ("True = 1\n", "True", 1),
+            ("None = 2\n", "None", 1),
+            ("False += 1\n", "False", 1),
+            ("for None in [1]:\n    x = 1\n", "None", 5),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            assert_eq!(err.msg, format!("cannot assign to {literal}"));
+            assert!(err.to_string().contains(&format!("(unset:1:{col})")));
+        }
+
+        assert!(Parser::new().parse_from_str("x = None\n").is_ok());
+    }
+
+    #[test]
+    fn test_chained_assignment() {
+        assert!(Parser::new().parse_from_str("a = b = c = 0\n").is_ok());
+        assert!(
+            Parser::new()
+                .parse_from_str("l = [0]\na = l[0] = 1\n")
+                .is_ok()
+        );
+
+        for (source, msg, col) in [
+            ("a = b + 1 = 2\n", "cannot assign to an expression", 5),
+            ("a = f() = 2\n", "cannot assign to a function call", 5),
+            ("a = 1 = 2\n", "cannot assign to an expression", 5),
+            (
+                "a += b = 2\n",
+                "only `=` can be used in a chained assignment",
+                3,
+            ),
+            (
+                "a = b -= 2\n",
+                "only `=` can be used in a chained assignment",
+                7,
+            ),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            assert_eq!(err.msg, msg);
+            assert!(err.to_string().contains(&format!("(unset:1:{col})")));
+        }
+    }
+
+    #[test]
+    fn test_error_underlines_whole_token() {
+        colored::control::set_override(false);
+        for (source, col, width) in [
+            ("a = b -= 2\n", 6, 2),
+            ("x := 1\n", 2, 2),
+            ("x = 1 not 2\n", 6, 3),
+            ("x = 1 ~ 2\n", 6, 1),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            // The caret line is indented past the `  | ` that the source line is printed after
+            let underline = format!("\n{}{}", " ".repeat(col + 4), "^".repeat(width));
+            assert!(err.to_string().ends_with(&underline), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_unexpected_character() {
+        for (source, c, col) in [
+            ("x = 1 ? 2\n", '?', 7),
+            ("x = $\n", '$', 5),
+            ("$x = 1\n", '$', 1),
+            ("print(1 ? 2)\n", '?', 9),
+            ("x.y = 1\n", '.', 2),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            assert_eq!(err.msg, format!("unexpected character '{c}'"));
+            assert!(err.to_string().contains(&format!("(unset:1:{col})")));
+        }
+
+        // Characters the grammar does use keep the usual message
+        let err = Parser::new().parse_from_str("x = 1, 2\n").err().unwrap();
+        assert_eq!(err.msg, "expected a newline");
+    }
+
+    #[test]
+    fn test_in_line_jumps() {
+        for source in [
+            "while x: break\n",
+            "for i in l:\n    if y: continue\n",
+            "def f():\n    if x: return\n    if y: return 1\n",
+        ] {
+            assert!(Parser::new().parse_from_str(source).is_ok(), "{source}");
+        }
+
+        // Jumps the context doesn't allow are named as such, whether in-line or on their own line
+        for (source, msg, position) in [
+            ("if x: break\n", "`break` outside loop", "(unset:1:7)"),
+            (
+                "if x: continue\n",
+                "`continue` not properly in loop",
+                "(unset:1:7)",
+            ),
+            (
+                "if x: return 1\n",
+                "`return` outside function",
+                "(unset:1:7)",
+            ),
+            ("break\n", "`break` outside loop", "(unset:1:1)"),
+            (
+                "while x:\n    def f():\n        continue\n",
+                "`continue` not properly in loop",
+                "(unset:3:9)",
+            ),
+            (
+                "if x:\n    return\n",
+                "`return` outside function",
+                "(unset:2:5)",
+            ),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            assert_eq!(err.msg, msg, "{source}");
+            assert!(err.to_string().contains(position), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_def_in_expression() {
+        for (source, position) in [
+            ("x = def f(): return 1\n", "(unset:1:5)"),
+            ("print(def)\n", "(unset:1:7)"),
+            ("x = [1, def]\n", "(unset:1:9)"),
+            ("print(1, def)\n", "(unset:1:10)"),
+        ] {
+            let err = Parser::new().parse_from_str(source).err().unwrap();
+            assert_eq!(
+                err.msg,
+                "expected an expression, but `def` is a statement: define the function on its own line and use its name instead (there are no lambdas)"
+            );
+            assert!(err.to_string().contains(position), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_blank_lines_dropped_from_blocks() {
+        let (res, _) = Parser::new()
+            .parse_from_str("\nx = 1\n\n\nif x:\n\n    y = 2\n\n    z = 3\n\n")
+            .unwrap();
+        let AstNode::block(statements) = res.ast_node.comp else {
+            panic!("expected a block, got {:?}", res.ast_node.comp);
+        };
+        assert_eq!(statements.len(), 2);
+        assert!(matches!(statements[0].comp, AstNode::assign_op { .. }));
+        let AstNode::if_stmt { ref then, .. } = statements[1].comp else {
+            panic!("expected an if_stmt, got {:?}", statements[1].comp);
+        };
+        let AstNode::block(ref body) = then.comp else {
+            panic!("expected a block, got {:?}", then.comp);
+        };
+        assert_eq!(body.len(), 2);
+        assert!(
+            body.iter()
+                .all(|statement| matches!(statement.comp, AstNode::assign_op { .. }))
+        );
+    }
+
+    #[test]
+    fn test_comments_are_kept_out_of_the_grammar() {
+        let source = "# header\nx = [1,  # one\n     2]\nif x:  # check\n    y = 1\n";
+        let (with_comments, _) = Parser::new()
+            .with_comments()
+            .parse_from_str(source)
+            .unwrap();
+        let (without_comments, _) = Parser::new().parse_from_str(source).unwrap();
+        assert_eq!(
+            format!("{:?}", with_comments.ast_node),
+            format!("{:?}", without_comments.ast_node)
+        );
+    }
+}
@@ -7,3 +7,21 @@ pub use two_way_iterator::TwoWayIterator;
 use std::collections::HashMap;
 
 pub type Map<T> = HashMap<String, T>;
+
+/// How much stack the main thread gets on Linux, which limits on native recursion are sized for. Test threads only get
+/// 2MB, so tests that go near those limits run on a thread of this size instead.
+#[cfg(test)]
+const MAIN_THREAD_STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Runs `f` on a thread with as much stack as the main thread, see `MAIN_THREAD_STACK_SIZE`.
+#[cfg(test)]
+pub fn with_main_thread_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(MAIN_THREAD_STACK_SIZE)
+            .spawn_scoped(scope, f)
+            .expect("Couldn't spawn a test thread")
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    })
+}